@@ -0,0 +1,64 @@
+//! Compares buffered line reading against the memory-mapped `parse_file`.
+//!
+//! Run with `cargo bench --features mmap`.
+
+use clippings_parser::{parse_file, parse_reader};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+const ENTRIES: usize = 200_000;
+const ROUNDS: u32 = 5;
+
+fn write_fixture(path: &std::path::Path) {
+    let mut writer = BufWriter::new(File::create(path).unwrap());
+    for i in 0..ENTRIES {
+        writeln!(writer, "Book {} (Author {})", i % 500, i % 97).unwrap();
+        writeln!(
+            writer,
+            "- Your Highlight on page {} | Location {}-{} | Added on Monday, January 3, 2022 10:15:30 AM",
+            i % 300 + 1,
+            i,
+            i + 3
+        )
+        .unwrap();
+        writeln!(writer).unwrap();
+        writeln!(
+            writer,
+            "Highlighted passage number {} with some extra words to read.",
+            i
+        )
+        .unwrap();
+        writeln!(writer, "==========").unwrap();
+    }
+}
+
+fn measure<F: FnMut() -> usize>(mut run: F) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        assert_eq!(run(), ENTRIES);
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+fn main() {
+    let path = std::env::temp_dir().join("clippings_parser_mmap_bench.txt");
+    write_fixture(&path);
+
+    let buffered = measure(|| {
+        let file = File::open(&path).unwrap();
+        parse_reader(BufReader::new(file)).unwrap().len()
+    });
+    let mapped = measure(|| parse_file(&path).unwrap().len());
+
+    println!("buffered: {:?}", buffered);
+    println!("mmap:     {:?}", mapped);
+    println!(
+        "speedup:  {:.2}x",
+        buffered.as_secs_f64() / mapped.as_secs_f64()
+    );
+
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,13 @@
+//! Feeds arbitrary input through the parser; any panic is a bug.
+//!
+//! Run with `cargo +nightly fuzz run parse`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = clippings_parser::parse_bytes(data);
+    let _ = clippings_parser::parse_bytes_lossy(data);
+    let _ = clippings_parser::parse_reader_lenient(data);
+});
@@ -1,8 +1,267 @@
-use clap::Parser;
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
+#[cfg(feature = "encoding")]
+use clippings_parser::encoding::InputEncoding;
+#[cfg(feature = "serde")]
+use clippings_parser::export::Column;
+use clippings_parser::file_parser::validate_date_format;
+use clippings_parser::grouping::BookOrder;
+use clippings_parser::{EntryType, Location, MetaRegex};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(after_help = "\
+Exit status:
+  0  every entry parsed
+  1  an input could not be read or parsed, --lenient skipped every entry, or
+     the output could not be written
+  2  --lenient skipped some entries; the rest were processed")]
 pub struct Args {
-    #[arg(short, long)]
-    pub(crate) clippings: String,
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+    /// Clippings file or directory to search recursively, repeat for several
+    /// inputs; defaults to $CLIPPINGS_FILE, and `-` or no input at all reads
+    /// stdin
+    #[arg(short, long, global = true, env = "CLIPPINGS_FILE")]
+    pub(crate) clippings: Vec<PathBuf>,
+    /// In directories, read every `.txt` file instead of only `My Clippings.txt`
+    #[arg(long, global = true)]
+    pub(crate) all_txt: bool,
+    /// Do not print the parse summary on stderr
+    #[arg(short, long, global = true)]
+    pub(crate) quiet: bool,
+    /// Show a progress bar on stderr while parsing
+    #[arg(long, global = true)]
+    pub(crate) progress: bool,
+    /// Skip entries that fail to parse and report them on stderr
+    #[arg(long, global = true)]
+    pub(crate) lenient: bool,
+    /// Replace invalid UTF-8 with U+FFFD instead of failing
+    #[arg(long, global = true)]
+    pub(crate) lossy: bool,
+    /// Input encoding: `auto` or a label such as `utf-8` or `windows-1252`
+    #[cfg(feature = "encoding")]
+    #[arg(long, global = true)]
+    pub(crate) encoding: Option<InputEncoding>,
+    /// Regex for the second line of each entry, replacing the built-in one;
+    /// it needs the named groups kind, page, location and date, and may have
+    /// color
+    #[arg(long, global = true, value_name = "PATTERN")]
+    pub(crate) meta_regex: Option<MetaRegex>,
+    /// Read the whole first line of each entry as the title, for books whose
+    /// title ends in parentheses, such as `Brackets (a memoir)`, which would
+    /// otherwise be split into a title and an author
+    #[arg(long, global = true)]
+    pub(crate) no_author: bool,
+    /// Print, for each input, how many entries used each variant of the
+    /// format, such as `Location:` labels or translated dates, on stderr
+    #[arg(short, long, global = true)]
+    pub(crate) verbose: bool,
+    /// Print how long parsing took and the entries-per-second rate on stderr
+    #[arg(long, global = true)]
+    pub(crate) timing: bool,
+    /// Remove bidirectional control marks from titles and authors
+    #[arg(long, global = true)]
+    pub(crate) strip_bidi: bool,
+    /// Keep only entries whose author contains this text (case-insensitive)
+    #[arg(long, global = true)]
+    pub(crate) author: Option<String>,
+    /// Keep only entries of the book with exactly this title
+    #[arg(long, global = true)]
+    pub(crate) book: Option<String>,
+    /// Keep only entries of books whose title contains this text
+    /// (case-insensitive)
+    #[arg(long, global = true, conflicts_with = "book")]
+    pub(crate) book_contains: Option<String>,
+    /// Keep only entries of this kind
+    #[arg(long, global = true, value_enum)]
+    pub(crate) kind: Option<Kind>,
+    /// Keep only entries whose location overlaps this range (START-END or a
+    /// single location), in every book unless --book picks one
+    #[arg(long, global = true, value_name = "START-END")]
+    pub(crate) location: Option<Location>,
+    /// Keep only entries added on or after this date (YYYY-MM-DD)
+    #[arg(long, global = true)]
+    pub(crate) since: Option<NaiveDate>,
+    /// Keep only entries added on or before this date (YYYY-MM-DD)
+    #[arg(long, global = true)]
+    pub(crate) until: Option<NaiveDate>,
+    /// Drop highlights shorter than this many characters
+    #[arg(long, global = true)]
+    pub(crate) min_length: Option<usize>,
+    /// Apply --min-length to notes and bookmarks as well
+    #[arg(long, global = true, requires = "min_length")]
+    pub(crate) min_length_all_kinds: bool,
+    /// Keep only entries newer than the timestamp stored in this file, then
+    /// store the newest output entry's timestamp in it; a missing or empty
+    /// file keeps everything
+    #[arg(long, global = true, value_name = "STATEFILE")]
+    pub(crate) newer_than_file: Option<PathBuf>,
+    /// Drop books with fewer than N highlights; only highlights left by the
+    /// other filters count, and --random picks from the books that remain
+    #[arg(long, global = true, value_name = "N")]
+    pub(crate) min_highlights: Option<usize>,
+    /// Keep only the latest version of highlights edited on the device
+    #[arg(long, global = true)]
+    pub(crate) keep_latest: bool,
+    /// Keep one highlight per book and location start: the longest, or of
+    /// those the latest
+    #[arg(long, global = true)]
+    pub(crate) one_per_location: bool,
+    /// Join highlights of a book that follow each other into one passage,
+    /// allowing up to GAP locations between them (1 if not given)
+    #[arg(long, global = true, value_name = "GAP", num_args = 0..=1, default_missing_value = "1")]
+    pub(crate) stitch: Option<u64>,
+    /// Pick this many highlights at random from the filtered entries
+    #[arg(long, global = true, value_name = "N")]
+    pub(crate) random: Option<usize>,
+    /// Seed for --random to make the pick reproducible
+    #[arg(long, global = true, requires = "random")]
+    pub(crate) seed: Option<u64>,
+    /// Replace the text of highlights and notes with `[redacted: N chars]` in
+    /// every format, keeping the other fields
+    #[arg(long, global = true)]
+    pub(crate) redact: bool,
+    /// strftime format for dates in the debug output; JSON and CSV always use ISO-8601
+    #[arg(long, value_parser = parse_date_format)]
+    pub(crate) date_format: Option<String>,
+    /// Print only the number of entries left after filtering
+    #[arg(long)]
+    pub(crate) count: bool,
+    /// With --count, count the blocks between separators without parsing
+    /// them; filters are ignored and malformed entries are counted too
+    #[arg(long, requires = "count")]
+    pub(crate) fast: bool,
+    /// Leave out notes that repeat their highlight's text (nested-json only)
+    #[arg(long)]
+    pub(crate) drop_redundant_notes: bool,
+    /// Order of books in grouped exports
+    #[arg(long, value_enum, default_value_t = GroupSort::Title)]
+    pub(crate) group_sort: GroupSort,
+    /// Keep entries in input file order: filters still apply, but nothing is
+    /// sorted or grouped, so this cannot be combined with --group-sort, the
+    /// nested-json format or the merge, books and stats subcommands
+    #[arg(long, conflicts_with = "group_sort")]
+    pub(crate) preserve_order: bool,
+    /// Show bookmarks as location marker lines in the debug and text formats,
+    /// which leave them out by default; the other formats always include them
+    #[arg(long)]
+    pub(crate) include_bookmarks: bool,
+    /// Characters of text to keep in the line format before cutting it off
+    #[arg(long, value_name = "CHARS", default_value_t = 60)]
+    pub(crate) width: usize,
+    /// Wrap the json format in `{"version": 1, "generated_at": ..., "entries":
+    /// [...]}` instead of printing a bare array
+    #[cfg(feature = "serde")]
+    #[arg(long)]
+    pub(crate) envelope: bool,
+    /// Comma-separated columns for csv and tsv, from title, author, kind,
+    /// page, location_start, location_end, creation_date, text and id;
+    /// defaults to all but id
+    #[cfg(feature = "serde")]
+    #[arg(long, value_delimiter = ',')]
+    pub(crate) columns: Vec<Column>,
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = Format::Debug)]
+    pub(crate) format: Format,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List books with their number of entries
+    Books {
+        /// Also show the most recently added entry of each book
+        #[arg(long)]
+        latest: bool,
+    },
+    /// Show totals, the reading span and the longest highlighting streak
+    Stats,
+    /// Combine the inputs into one canonical My Clippings.txt: duplicates are
+    /// dropped and entries are ordered by title, author, location and date
+    Merge {
+        /// File to write the merged clippings to
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Treat titles and authors differing only in case or spacing as the
+        /// same book when dropping duplicates
+        #[arg(long)]
+        dedup_normalized: bool,
+    },
+    /// Show the entries added and removed between two clippings files, such
+    /// as last week's export and this week's; --book, --author, --kind,
+    /// --location, the date and the length filters apply to both, the other
+    /// filters are ignored
+    Diff {
+        /// The earlier file
+        old: PathBuf,
+        /// The later file
+        new: PathBuf,
+    },
+    /// Check that every entry parses, reporting failures with their line
+    /// number; exits non-zero when any input is invalid
+    Validate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One line per entry with all its fields; bookmarks are skipped
+    Debug,
+    /// Only the highlight texts, separated by blank lines
+    Text,
+    /// The My Clippings.txt format itself, including bookmarks
+    Clippings,
+    /// One short line per entry: kind, book, location, date and the start of
+    /// the text
+    Line,
+    #[cfg(feature = "serde")]
+    Json,
+    /// JSON grouped by book, with notes nested under their highlights
+    #[cfg(feature = "serde")]
+    NestedJson,
+    #[cfg(feature = "serde")]
+    Csv,
+    #[cfg(feature = "serde")]
+    Tsv,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GroupSort {
+    Title,
+    /// By author surname, then title
+    Author,
+}
+
+impl From<GroupSort> for BookOrder {
+    fn from(sort: GroupSort) -> Self {
+        match sort {
+            GroupSort::Title => BookOrder::Title,
+            GroupSort::Author => BookOrder::Author,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Kind {
+    Highlight,
+    Note,
+    Bookmark,
+    Article,
+}
+
+impl From<Kind> for EntryType {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Highlight => EntryType::Highlight,
+            Kind::Note => EntryType::Note,
+            Kind::Bookmark => EntryType::Bookmark,
+            Kind::Article => EntryType::Article,
+        }
+    }
+}
+
+fn parse_date_format(value: &str) -> Result<String, String> {
+    validate_date_format(value)
+        .map(|_| value.to_string())
+        .map_err(|err| err.to_string())
 }
@@ -0,0 +1,34 @@
+use crate::file_parser::{Entry, ParseError};
+use polars::prelude::{Column, DataFrame, NamedFrom, Series};
+
+/// One row per entry and one column per field. The creation date is a
+/// millisecond `Datetime` column and the location is split into
+/// `location_start` and `location_end`; `color` and `page` are null when
+/// absent.
+pub fn to_dataframe(entries: &[Entry]) -> Result<DataFrame, ParseError> {
+    fn column<T>(name: &str, entries: &[Entry], field: impl Fn(&Entry) -> T) -> Column
+    where
+        Series: NamedFrom<Vec<T>, [T]>,
+    {
+        Series::new(name.into(), entries.iter().map(field).collect::<Vec<T>>()).into()
+    }
+
+    let columns = vec![
+        column("title", entries, |entry| entry.title().to_string()),
+        column("author", entries, |entry| entry.author().to_string()),
+        column("kind", entries, |entry| format!("{:?}", entry.kind())),
+        column("color", entries, |entry| entry.color().map(str::to_string)),
+        column("page", entries, |entry| {
+            entry.page().map(|page| page.number())
+        }),
+        column("location_start", entries, |entry| {
+            entry.location().map(|location| location.start())
+        }),
+        column("location_end", entries, |entry| {
+            entry.location().map(|location| location.end())
+        }),
+        column("creation_date", entries, Entry::creation_date),
+        column("text", entries, |entry| entry.text().to_string()),
+    ];
+    Ok(DataFrame::new(entries.len(), columns)?)
+}
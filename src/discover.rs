@@ -0,0 +1,31 @@
+use crate::file_parser::ParseError;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+const CLIPPINGS_FILE_NAME: &str = "My Clippings.txt";
+
+/// Recursively collects `My Clippings.txt` files under `dir`, or every `.txt`
+/// file when `any_txt` is set. Paths are returned sorted.
+pub fn find_clippings_files(dir: &Path, any_txt: bool) -> Result<Vec<PathBuf>, ParseError> {
+    let mut files = vec![];
+    for item in WalkDir::new(dir) {
+        let item = item.map_err(io::Error::from)?;
+        if !item.file_type().is_file() {
+            continue;
+        }
+        let path = item.path();
+        let matches = if any_txt {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"))
+        } else {
+            path.file_name()
+                .is_some_and(|name| name == CLIPPINGS_FILE_NAME)
+        };
+        if matches {
+            files.push(path.to_path_buf());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
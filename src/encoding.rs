@@ -0,0 +1,56 @@
+use crate::file_parser::{parse_str, Entry, ParseError};
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use std::borrow::Cow;
+use std::str::FromStr;
+
+/// How to turn input bytes into text before parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEncoding {
+    /// Honour a byte order mark, otherwise use UTF-8 when the bytes are valid
+    /// UTF-8 and Windows-1252 when they are not.
+    Detect,
+    Fixed(&'static Encoding),
+}
+
+impl FromStr for InputEncoding {
+    type Err = String;
+
+    /// Accepts `auto` or any WHATWG encoding label such as `utf-8`,
+    /// `windows-1252` or `latin1`.
+    fn from_str(label: &str) -> Result<Self, Self::Err> {
+        if label.eq_ignore_ascii_case("auto") {
+            return Ok(InputEncoding::Detect);
+        }
+        Encoding::for_label(label.as_bytes())
+            .map(InputEncoding::Fixed)
+            .ok_or_else(|| format!("unknown encoding {}", label))
+    }
+}
+
+/// Picks the encoding [`InputEncoding::Detect`] would use for `bytes`.
+pub fn detect(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        encoding
+    } else if std::str::from_utf8(bytes).is_ok() {
+        UTF_8
+    } else {
+        WINDOWS_1252
+    }
+}
+
+/// Decodes `bytes` to UTF-8, stripping a byte order mark. Sequences that are
+/// invalid in the chosen encoding become U+FFFD.
+pub fn decode(bytes: &[u8], encoding: InputEncoding) -> Cow<'_, str> {
+    let encoding = match encoding {
+        InputEncoding::Detect => detect(bytes),
+        InputEncoding::Fixed(encoding) => encoding,
+    };
+    encoding.decode_with_bom_removal(bytes).0
+}
+
+pub fn parse_bytes_with_encoding(
+    bytes: &[u8],
+    encoding: InputEncoding,
+) -> Result<Vec<Entry>, ParseError> {
+    parse_str(&decode(bytes, encoding))
+}
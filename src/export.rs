@@ -0,0 +1,266 @@
+use crate::file_parser::{Entry, EntryType, ParseError};
+use crate::grouping::{attach_notes, group_by_book, Annotation, Book};
+use crate::schema::Envelope;
+use crate::stats::{max_location, percent_through};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::str::FromStr;
+
+pub fn to_json(entries: &[Entry]) -> Result<String, ParseError> {
+    Ok(serde_json::to_string_pretty(entries)?)
+}
+
+/// Like [`to_json`], with the array wrapped in a versioned
+/// [`Envelope`](crate::schema::Envelope).
+pub fn to_json_envelope(entries: &[Entry]) -> Result<String, ParseError> {
+    Ok(serde_json::to_string_pretty(&Envelope::new(entries))?)
+}
+
+/// Writes a JSON array of the entries as they are pulled from `entries`, one
+/// compact element per line, so nothing is buffered. Meant for
+/// [`parse_reader_iter`](crate::parse_reader_iter); the first parse error
+/// stops the output and is returned, leaving the array unterminated.
+pub fn write_json_stream<W: Write>(
+    entries: impl Iterator<Item = Result<Entry, ParseError>>,
+    mut writer: W,
+) -> Result<(), ParseError> {
+    writer.write_all(b"[")?;
+    for (index, entry) in entries.enumerate() {
+        let entry = entry?;
+        writer.write_all(if index == 0 { b"\n" } else { b",\n" })?;
+        serde_json::to_writer(&mut writer, &entry)?;
+    }
+    writer.write_all(b"\n]\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct NestedBook {
+    title: String,
+    author: String,
+    highlights: Vec<NestedHighlight>,
+}
+
+#[derive(Serialize)]
+struct NestedHighlight {
+    #[serde(flatten)]
+    annotation: Annotation,
+    percent_through: f64,
+}
+
+/// Serializes the entries as an array of books, each holding its highlights
+/// with the attached notes nested under them.
+pub fn to_nested_json(entries: &[Entry]) -> Result<String, ParseError> {
+    to_nested_json_with(entries, |annotations| annotations)
+}
+
+/// Like [`to_nested_json`], passing each book's annotations through
+/// `transform` first, e.g. [`drop_redundant_notes`](crate::grouping::drop_redundant_notes).
+pub fn to_nested_json_with<F>(entries: &[Entry], transform: F) -> Result<String, ParseError>
+where
+    F: FnMut(Vec<Annotation>) -> Vec<Annotation>,
+{
+    books_to_nested_json(group_by_book(entries.to_vec()), transform)
+}
+
+/// Nested JSON for books that were already grouped, keeping their order.
+pub fn books_to_nested_json<F>(books: Vec<Book>, mut transform: F) -> Result<String, ParseError>
+where
+    F: FnMut(Vec<Annotation>) -> Vec<Annotation>,
+{
+    let books: Vec<NestedBook> = books
+        .into_iter()
+        .map(|book| {
+            let max = max_location(&book);
+            let highlights = transform(attach_notes(book.entries))
+                .into_iter()
+                .map(|annotation| NestedHighlight {
+                    percent_through: percent_through(annotation.highlight.position().start(), max),
+                    annotation,
+                })
+                .collect();
+            NestedBook {
+                title: book.title,
+                author: book.author,
+                highlights,
+            }
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&books)?)
+}
+
+impl Entry {
+    pub fn to_json_value(&self) -> Value {
+        Value::from(self)
+    }
+}
+
+impl From<&Entry> for Value {
+    fn from(entry: &Entry) -> Self {
+        serde_json::to_value(entry).expect("entry fields always serialize to JSON")
+    }
+}
+
+/// Row-shaped view of an [`Entry`] with only scalar fields, for CSV/TSV and
+/// other tabular consumers.
+#[derive(Debug, Serialize)]
+pub struct FlatEntry<'a> {
+    pub title: &'a str,
+    pub author: &'a str,
+    pub kind: &'a EntryType,
+    pub page: Option<u64>,
+    pub location_start: Option<u64>,
+    pub location_end: Option<u64>,
+    pub creation_date: NaiveDateTime,
+    pub text: &'a str,
+}
+
+impl<'a> From<&'a Entry> for FlatEntry<'a> {
+    fn from(entry: &'a Entry) -> Self {
+        FlatEntry {
+            title: entry.title(),
+            author: entry.author(),
+            kind: entry.kind(),
+            page: entry.page().map(|page| page.number()),
+            location_start: entry.location().map(|location| location.start()),
+            location_end: entry.location().map(|location| location.end()),
+            creation_date: entry.creation_date(),
+            text: entry.text(),
+        }
+    }
+}
+
+/// A column of the CSV and TSV exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Title,
+    Author,
+    Kind,
+    Page,
+    LocationStart,
+    LocationEnd,
+    CreationDate,
+    Text,
+    /// [`Entry::id`]
+    Id,
+}
+
+impl Column {
+    /// Every column, in the order the names are listed in errors and help.
+    pub const ALL: [Column; 9] = [
+        Column::Title,
+        Column::Author,
+        Column::Kind,
+        Column::Page,
+        Column::LocationStart,
+        Column::LocationEnd,
+        Column::CreationDate,
+        Column::Text,
+        Column::Id,
+    ];
+
+    /// The columns of [`FlatEntry`], which [`to_csv`] and [`to_tsv`] write.
+    pub const DEFAULT: [Column; 8] = [
+        Column::Title,
+        Column::Author,
+        Column::Kind,
+        Column::Page,
+        Column::LocationStart,
+        Column::LocationEnd,
+        Column::CreationDate,
+        Column::Text,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Title => "title",
+            Column::Author => "author",
+            Column::Kind => "kind",
+            Column::Page => "page",
+            Column::LocationStart => "location_start",
+            Column::LocationEnd => "location_end",
+            Column::CreationDate => "creation_date",
+            Column::Text => "text",
+            Column::Id => "id",
+        }
+    }
+
+    /// `title, author, ...`
+    pub fn names() -> String {
+        Column::ALL.map(|column| column.name()).join(", ")
+    }
+
+    /// The cell for `entry`, formatted as serde would format the field.
+    fn value(&self, entry: &Entry) -> String {
+        match self {
+            Column::Title => entry.title().to_string(),
+            Column::Author => entry.author().to_string(),
+            Column::Kind => format!("{:?}", entry.kind()),
+            Column::Page => entry
+                .page()
+                .map(|page| page.number().to_string())
+                .unwrap_or_default(),
+            Column::LocationStart => entry
+                .location()
+                .map(|location| location.start().to_string())
+                .unwrap_or_default(),
+            Column::LocationEnd => entry
+                .location()
+                .map(|location| location.end().to_string())
+                .unwrap_or_default(),
+            Column::CreationDate => format!("{:?}", entry.creation_date()),
+            Column::Text => entry.text().to_string(),
+            Column::Id => entry.id(),
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        Column::ALL
+            .into_iter()
+            .find(|column| column.name() == name)
+            .ok_or_else(|| ParseError::UnknownColumn(name.to_string()))
+    }
+}
+
+pub fn to_csv(entries: &[Entry]) -> Result<String, ParseError> {
+    to_delimited(entries, &Column::DEFAULT, b',')
+}
+
+pub fn to_tsv(entries: &[Entry]) -> Result<String, ParseError> {
+    to_delimited(entries, &Column::DEFAULT, b'\t')
+}
+
+/// CSV with only `columns`, in that order.
+pub fn to_csv_with_columns(entries: &[Entry], columns: &[Column]) -> Result<String, ParseError> {
+    to_delimited(entries, columns, b',')
+}
+
+pub fn to_tsv_with_columns(entries: &[Entry], columns: &[Column]) -> Result<String, ParseError> {
+    to_delimited(entries, columns, b'\t')
+}
+
+fn to_delimited(
+    entries: &[Entry],
+    columns: &[Column],
+    delimiter: u8,
+) -> Result<String, ParseError> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(vec![]);
+    writer.write_record(columns.iter().map(Column::name))?;
+    for entry in entries {
+        writer.write_record(columns.iter().map(|column| column.value(entry)))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|err| csv::Error::from(err.into_error()))?;
+    Ok(String::from_utf8(bytes).expect("CSV output is built from UTF-8 strings"))
+}
@@ -0,0 +1,218 @@
+use crate::file_parser::{Entry, EntryType, ParseError};
+use crate::render;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+#[cfg(feature = "serde")]
+use crate::export::{self, Column};
+#[cfg(feature = "serde")]
+use crate::grouping::{self, BookOrder};
+
+/// An output format. The CLI looks formats up by name in a [`Registry`], and
+/// library users can register their own next to the built-in ones, either as
+/// a type or as a closure:
+///
+/// ```
+/// use clippings_parser::exporter::{Exporter, Registry};
+/// use clippings_parser::{parse_str, Entry, ParseError};
+/// use std::io::Write;
+///
+/// let mut registry = Registry::new();
+/// registry.register("titles", |entries: &[Entry], writer: &mut dyn Write| {
+///     for entry in entries {
+///         writeln!(writer, "{}", entry.title())?;
+///     }
+///     Ok::<_, ParseError>(())
+/// });
+///
+/// let entries = parse_str("\
+/// Dune (Frank Herbert)
+/// - Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+///
+/// Fear is the mind-killer.
+/// ==========
+/// ").unwrap();
+/// let mut out = vec![];
+/// registry.get("titles").unwrap().export(&entries, &mut out).unwrap();
+/// assert_eq!(out, b"Dune\n");
+/// ```
+pub trait Exporter {
+    fn export(&self, entries: &[Entry], writer: &mut dyn Write) -> Result<(), ParseError>;
+}
+
+impl<F> Exporter for F
+where
+    F: Fn(&[Entry], &mut dyn Write) -> Result<(), ParseError>,
+{
+    fn export(&self, entries: &[Entry], writer: &mut dyn Write) -> Result<(), ParseError> {
+        self(entries, writer)
+    }
+}
+
+/// Exporters by name, listed in name order.
+#[derive(Default)]
+pub struct Registry {
+    exporters: BTreeMap<String, Box<dyn Exporter>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `exporter` under `name`, replacing any exporter already there.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        exporter: impl Exporter + 'static,
+    ) -> &mut Self {
+        self.exporters.insert(name.into(), Box::new(exporter));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Exporter> {
+        self.exporters.get(name).map(|exporter| exporter.as_ref())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.exporters.keys().map(String::as_str)
+    }
+}
+
+/// One line per entry with all its fields, the `Display` of [`Entry`].
+/// Bookmarks are skipped unless `include_bookmarks` is set, in which case
+/// they show as a [`render::bookmark_marker`].
+pub struct DebugExporter {
+    pub include_bookmarks: bool,
+    /// `strftime` format for the creation date, which must pass
+    /// [`validate_date_format`](crate::file_parser::validate_date_format).
+    pub date_format: Option<String>,
+    /// Pass each line through [`render::escape_control`], for terminals.
+    pub escape_control: bool,
+}
+
+impl Exporter for DebugExporter {
+    fn export(&self, entries: &[Entry], writer: &mut dyn Write) -> Result<(), ParseError> {
+        for entry in entries {
+            let bookmark = *entry.kind() == EntryType::Bookmark;
+            if bookmark && !self.include_bookmarks {
+                continue;
+            }
+            let line = match &self.date_format {
+                _ if bookmark => render::bookmark_marker(entry),
+                Some(date_format) => entry.display_with_date_format(date_format).to_string(),
+                None => entry.to_string(),
+            };
+            if self.escape_control {
+                writeln!(writer, "{}", render::escape_control(&line))?;
+            } else {
+                writeln!(writer, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// [`render::to_text_with`].
+pub struct TextExporter {
+    pub include_bookmarks: bool,
+}
+
+impl Exporter for TextExporter {
+    fn export(&self, entries: &[Entry], writer: &mut dyn Write) -> Result<(), ParseError> {
+        let text = render::to_text_with(entries, self.include_bookmarks);
+        Ok(writer.write_all(text.as_bytes())?)
+    }
+}
+
+/// [`render::to_line`] for each entry.
+pub struct LineExporter {
+    pub width: usize,
+}
+
+impl Exporter for LineExporter {
+    fn export(&self, entries: &[Entry], writer: &mut dyn Write) -> Result<(), ParseError> {
+        for entry in entries {
+            writeln!(writer, "{}", render::to_line(entry, self.width))?;
+        }
+        Ok(())
+    }
+}
+
+/// [`render::to_clippings`].
+pub struct ClippingsExporter;
+
+impl Exporter for ClippingsExporter {
+    fn export(&self, entries: &[Entry], writer: &mut dyn Write) -> Result<(), ParseError> {
+        Ok(writer.write_all(render::to_clippings(entries)?.as_bytes())?)
+    }
+}
+
+/// [`export::to_json`], or [`export::to_json_envelope`] with `envelope`.
+#[cfg(feature = "serde")]
+pub struct JsonExporter {
+    pub envelope: bool,
+}
+
+#[cfg(feature = "serde")]
+impl Exporter for JsonExporter {
+    fn export(&self, entries: &[Entry], writer: &mut dyn Write) -> Result<(), ParseError> {
+        let json = if self.envelope {
+            export::to_json_envelope(entries)?
+        } else {
+            export::to_json(entries)?
+        };
+        Ok(writeln!(writer, "{}", json)?)
+    }
+}
+
+/// [`export::books_to_nested_json`] with the books in `order`.
+#[cfg(feature = "serde")]
+pub struct NestedJsonExporter {
+    pub order: BookOrder,
+    /// Leave out notes that repeat their highlight, see
+    /// [`grouping::drop_redundant_notes`].
+    pub drop_redundant_notes: bool,
+}
+
+#[cfg(feature = "serde")]
+impl Exporter for NestedJsonExporter {
+    fn export(&self, entries: &[Entry], writer: &mut dyn Write) -> Result<(), ParseError> {
+        let mut books = grouping::group_by_book(entries.to_vec());
+        grouping::sort_books(&mut books, self.order);
+        let nested = if self.drop_redundant_notes {
+            export::books_to_nested_json(books, grouping::drop_redundant_notes)?
+        } else {
+            export::books_to_nested_json(books, |annotations| annotations)?
+        };
+        Ok(writeln!(writer, "{}", nested)?)
+    }
+}
+
+/// [`export::to_csv_with_columns`].
+#[cfg(feature = "serde")]
+pub struct CsvExporter {
+    pub columns: Vec<Column>,
+}
+
+#[cfg(feature = "serde")]
+impl Exporter for CsvExporter {
+    fn export(&self, entries: &[Entry], writer: &mut dyn Write) -> Result<(), ParseError> {
+        let csv = export::to_csv_with_columns(entries, &self.columns)?;
+        Ok(writer.write_all(csv.as_bytes())?)
+    }
+}
+
+/// [`export::to_tsv_with_columns`].
+#[cfg(feature = "serde")]
+pub struct TsvExporter {
+    pub columns: Vec<Column>,
+}
+
+#[cfg(feature = "serde")]
+impl Exporter for TsvExporter {
+    fn export(&self, entries: &[Entry], writer: &mut dyn Write) -> Result<(), ParseError> {
+        let tsv = export::to_tsv_with_columns(entries, &self.columns)?;
+        Ok(writer.write_all(tsv.as_bytes())?)
+    }
+}
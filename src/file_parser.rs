@@ -1,24 +1,77 @@
-use crate::file_parser::EntryType::{Bookmark, Highlight, Note};
-use chrono::NaiveDateTime;
+use crate::file_parser::EntryType::{Article, Bookmark, Highlight, Note};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
 use itertools::Itertools;
 use regex::Regex;
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Lines};
-use std::path::Path;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::OnceLock;
 use std::{fmt, io};
 use thiserror::Error;
 
-#[derive(Debug)]
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Location(u64, u64);
 
+impl TryFrom<(u64, u64)> for Location {
+    type Error = ParseError;
+
+    /// Builds a location range, rejecting ranges that end before they start.
+    fn try_from((start, end): (u64, u64)) -> Result<Self, Self::Error> {
+        if start <= end {
+            Ok(Location(start, end))
+        } else {
+            Err(ParseError::InvalidLocation(format!("{}-{}", start, end)))
+        }
+    }
+}
+
+impl Location {
+    pub fn start(&self) -> u64 {
+        self.0
+    }
+
+    pub fn end(&self) -> u64 {
+        self.1
+    }
+
+    pub fn contains(&self, location: u64) -> bool {
+        self.0 <= location && location <= self.1
+    }
+
+    /// Whether the two ranges share at least one location.
+    pub fn overlaps(&self, other: &Location) -> bool {
+        self.0 <= other.1 && other.0 <= self.1
+    }
+
+    /// How far `location` lies outside the range, `0` when it is inside.
+    pub fn distance(&self, location: u64) -> u64 {
+        if location < self.0 {
+            self.0 - location
+        } else {
+            location.saturating_sub(self.1)
+        }
+    }
+}
+
+/// Accepts a single location or a `start-end` range. Some exports use a
+/// Unicode dash such as `–` instead of the hyphen, which is accepted too.
 impl FromStr for Location {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let separated: Result<Vec<u64>, Self::Err> = s
-            .split('-')
+            .split([
+                '-', '\u{2010}', '\u{2011}', '\u{2012}', '\u{2013}', '\u{2014}', '\u{2212}',
+            ])
             .map(|part| {
                 part.parse::<u64>()
                     .map_err(|_| ParseError::InvalidLocation(part.to_string()))
@@ -27,7 +80,7 @@ impl FromStr for Location {
 
         match separated {
             Ok(values) => match values.len() {
-                2 => Ok(Location(values[0], values[1])),
+                2 => Location::try_from((values[0], values[1])),
                 1 => Ok(Location(values[0], values[0])),
                 _ => Err(ParseError::InvalidLocation(s.to_string())),
             },
@@ -36,9 +89,22 @@ impl FromStr for Location {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(transparent))]
 pub struct Page(u64);
 
+impl From<u64> for Page {
+    fn from(value: u64) -> Self {
+        Page(value)
+    }
+}
+
+impl Page {
+    pub fn number(&self) -> u64 {
+        self.0
+    }
+}
+
 impl FromStr for Page {
     type Err = ParseError;
 
@@ -50,11 +116,14 @@ impl FromStr for Page {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum EntryType {
     Highlight,
     Note,
     Bookmark,
+    /// A web article clipped to the device.
+    Article,
 }
 
 impl FromStr for EntryType {
@@ -62,44 +131,314 @@ impl FromStr for EntryType {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "Highlight" => Ok(Highlight),
+            // Some firmware writes "- My Clipping" for highlights.
+            "Highlight" | "Clipping" => Ok(Highlight),
             "Note" => Ok(Note),
             "Bookmark" => Ok(Bookmark),
+            "Article" => Ok(Article),
             _ => Err(ParseError::InvalidKind(s.to_string())),
         }
     }
 }
 
+/// How much of [`Entry::creation_date`] came from the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DatePrecision {
+    #[default]
+    DateTime,
+    /// Only the day was given; the time is midnight.
+    Date,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Entry {
-    title: String,
-    author: String,
-    kind: EntryType,
-    page: Option<Page>,
-    location: Location,
-    creation_date: NaiveDateTime,
-    text: String,
+    pub(crate) title: String,
+    pub(crate) author: String,
+    pub(crate) kind: EntryType,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(crate) color: Option<String>,
+    pub(crate) page: Option<Page>,
+    pub(crate) location: Option<Location>,
+    pub(crate) creation_date: NaiveDateTime,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) date_precision: DatePrecision,
+    pub(crate) text: String,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) source_offset: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) raw_meta: Option<String>,
+}
+
+impl Entry {
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    /// The individual authors of a co-written book, split on `;`, ` and `
+    /// and ` & `. Names that merely contain "and" stay whole. Commas are not
+    /// split on, since a list of names cannot be told apart from Kindle's
+    /// surname-first form such as `Le Guin, Ursula K.`.
+    pub fn authors(&self) -> Vec<&str> {
+        self.author
+            .split(';')
+            .flat_map(|part| part.split(" and "))
+            .flat_map(|part| part.split(" & "))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    pub fn kind(&self) -> &EntryType {
+        &self.kind
+    }
+
+    /// Highlight color recorded by newer firmware, e.g. `yellow`.
+    pub fn color(&self) -> Option<&str> {
+        self.color.as_deref()
+    }
+
+    pub fn page(&self) -> Option<&Page> {
+        self.page.as_ref()
+    }
+
+    /// `None` for fixed-layout books, which only record a page.
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// Where the entry sits in the book, for sorting and progress: its
+    /// location, or for books without locations its page as a one-page
+    /// range. An entry with neither sits at `0`.
+    pub fn position(&self) -> Location {
+        match (self.location, self.page) {
+            (Some(location), _) => location,
+            (None, Some(page)) => Location(page.number(), page.number()),
+            (None, None) => Location(0, 0),
+        }
+    }
+
+    /// `(title, start, end)` of the entry's [`position`](Entry::position),
+    /// the key the CLI orders entries by. Entries without a location sort by
+    /// page among located ones, and entries with neither come first.
+    /// [`sort_by_book_and_location`](crate::grouping::sort_by_book_and_location)
+    /// and [`stitch_adjacent`](crate::grouping::stitch_adjacent) build on it;
+    /// the former also keeps books that share a title apart by author, before
+    /// positions are compared.
+    pub fn sort_key(&self) -> (String, u64, u64) {
+        let position = self.position();
+        (self.title.clone(), position.start(), position.end())
+    }
+
+    pub fn creation_date(&self) -> NaiveDateTime {
+        self.creation_date
+    }
+
+    pub fn date_precision(&self) -> DatePrecision {
+        self.date_precision
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Byte offset of the entry's block in the file it was parsed from, or
+    /// `None` when it came from a reader or string.
+    pub fn source_offset(&self) -> Option<u64> {
+        self.source_offset
+    }
+
+    /// The entry as a `My Clippings.txt` block without the separator: the
+    /// title line, the metadata line, a blank line and the text. Unlike
+    /// `Display`, this is what [`parse_block`] reads, so parsing its lines
+    /// gives back an equal entry:
+    ///
+    /// ```
+    /// use clippings_parser::{parse_block, parse_str};
+    ///
+    /// let entries = parse_str("\
+    /// Dune (Frank Herbert)
+    /// - Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+    ///
+    /// Fear is the mind-killer.
+    /// ==========
+    /// ").unwrap();
+    /// let block = entries[0].to_block_string();
+    /// let lines: Vec<&str> = block.lines().collect();
+    /// assert_eq!(parse_block(&lines).unwrap(), entries[0]);
+    /// ```
+    pub fn to_block_string(&self) -> String {
+        let mut block = String::new();
+        crate::render::write_block(&mut block, self);
+        block
+    }
+
+    /// The entry with its text replaced, for tools that clean up or edit
+    /// highlights before writing them out again.
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+        self
+    }
+
+    /// A stable identifier derived from the book, kind, location and creation
+    /// date: the same clipping gets the same id in every export, and an edited
+    /// highlight, which Kindle saves with a new date, gets a new one. It is the
+    /// 64-bit FNV-1a hash of those fields, as 16 hex digits.
+    pub fn id(&self) -> String {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let key = format!(
+            "{}\0{}\0{:?}\0{}-{}\0{}",
+            self.title,
+            self.author,
+            self.kind,
+            self.position().start(),
+            self.position().end(),
+            self.creation_date.format("%Y-%m-%dT%H:%M:%S")
+        );
+        let hash = key.bytes().fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        });
+        format!("{:016x}", hash)
+    }
+
+    /// The metadata line (`- Your Highlight on ...`) exactly as it appeared in
+    /// the input, for diagnosing entries that parsed unexpectedly.
+    pub fn raw_meta(&self) -> Option<&str> {
+        self.raw_meta.as_deref()
+    }
 }
 
 impl Display for Entry {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{:?} ({}) in {}: {} - page: {:?} location: {:?}, text: {}",
+        self.display_with_date_format(DEFAULT_DATE_FORMAT).fmt(f)
+    }
+}
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// How Kindle writes dates after "Added on" in English.
+pub(crate) const KINDLE_DATE_FORMAT: &str = "%A, %B %-e, %Y %-l:%M:%S %p";
+
+/// Entries are equal when they say the same thing: every parsed field counts,
+/// but not where the entry was read from ([`Entry::source_offset`],
+/// [`Entry::raw_meta`]), so the same clipping from two devices compares equal.
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.content() == other.content()
+    }
+}
+
+impl Eq for Entry {}
+
+impl Hash for Entry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.content().hash(state)
+    }
+}
+
+pub(crate) type EntryContent<'a> = (
+    &'a str,
+    &'a str,
+    EntryType,
+    Option<&'a str>,
+    Option<Page>,
+    Option<Location>,
+    NaiveDateTime,
+    &'a str,
+);
+
+impl Entry {
+    pub(crate) fn content(&self) -> EntryContent<'_> {
+        (
+            &self.title,
+            &self.author,
             self.kind,
-            self.creation_date,
-            self.author,
-            self.title,
+            self.color.as_deref(),
             self.page,
             self.location,
-            self.text
+            self.creation_date,
+            &self.text,
+        )
+    }
+}
+
+/// [`Display`] adapter rendering an entry with a custom `strftime` date
+/// format, see [`Entry::display_with_date_format`].
+pub struct EntryDisplay<'a> {
+    entry: &'a Entry,
+    date_format: &'a str,
+}
+
+impl Entry {
+    /// Renders the entry like its `Display` impl, formatting the creation date
+    /// with `date_format`. The format must pass [`validate_date_format`].
+    pub fn display_with_date_format<'a>(&'a self, date_format: &'a str) -> EntryDisplay<'a> {
+        EntryDisplay {
+            entry: self,
+            date_format,
+        }
+    }
+}
+
+impl Display for EntryDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let entry = self.entry;
+        write!(
+            f,
+            "{:?} ({}) in {}: {} - page: {:?} location: {:?}, text: {}",
+            entry.kind,
+            entry.creation_date.format(self.date_format),
+            entry.author,
+            entry.title,
+            entry.page,
+            LocationDebug(entry.location),
+            entry.text
         )
     }
 }
 
+/// Prints a present location as before locations became optional, and
+/// `None` for page-only entries.
+struct LocationDebug(Option<Location>);
+
+impl fmt::Debug for LocationDebug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(location) => location.fmt(f),
+            None => f.write_str("None"),
+        }
+    }
+}
+
+/// Checks that `date_format` only contains `strftime` items chrono understands
+/// and can fill in from a creation date, which has no time zone: `%Z` and
+/// `%z` parse but cannot be formatted.
+pub fn validate_date_format(date_format: &str) -> Result<(), ParseError> {
+    use std::fmt::Write;
+
+    let invalid = || ParseError::InvalidDateFormat(date_format.to_string());
+    if StrftimeItems::new(date_format).any(|item| matches!(item, Item::Error)) {
+        return Err(invalid());
+    }
+    let sample = NaiveDateTime::default();
+    write!(String::new(), "{}", sample.format(date_format)).map_err(|_| invalid())
+}
+
+/// Every error this crate returns. It is `Send + Sync + 'static`, so it
+/// converts into `Box<dyn Error + Send + Sync>` or `anyhow::Error` with `?`;
+/// wrapped errors must keep those bounds.
 #[derive(Error, Debug)]
 pub enum ParseError {
     #[error("IO error during reading the file")]
     FileReadError(#[from] io::Error),
+    #[error("Cannot open {}", path.display())]
+    FileOpen { path: PathBuf, source: io::Error },
     #[error("Title is not found")]
     TitleNotFound,
     #[error("Author is not found")]
@@ -110,7 +449,11 @@ pub enum ParseError {
     LocationNotFound,
     #[error("Date is not found")]
     DateNotFound,
-    #[error("Invalid entry type {0}, must be Highlight or Note")]
+    #[error("Text is not found, the entry is truncated")]
+    TextNotFound,
+    #[error("Malformed entry at line: {0}")]
+    MalformedEntry(String),
+    #[error("Invalid entry type {0}, must be Highlight, Note, Bookmark or Article")]
     InvalidKind(String),
     #[error("Invalid page number {0}")]
     InvalidPage(String),
@@ -118,78 +461,781 @@ pub enum ParseError {
     InvalidLocation(String),
     #[error("Invalid date {0}")]
     InvalidDate(String),
+    #[error("Invalid date format {0}")]
+    InvalidDateFormat(String),
+    #[error("Invalid metadata regex: {0}")]
+    InvalidMetaRegex(String),
+    #[error("Text of an entry in {0} has a line that would read as a separator")]
+    SeparatorInText(String),
+    #[error("Invalid UTF-8 after byte {valid_up_to}")]
+    InvalidUtf8 { valid_up_to: usize },
+    #[cfg(feature = "serde")]
+    #[error("Serialization failed")]
+    SerializeError(#[from] serde_json::Error),
+    #[cfg(feature = "serde")]
+    #[error("CSV serialization failed")]
+    CsvError(#[from] csv::Error),
+    #[cfg(feature = "sidecar")]
+    #[error("Invalid annotation sidecar")]
+    InvalidSidecar(#[source] serde_json::Error),
+    #[cfg(feature = "serde")]
+    #[error(
+        "Unknown column {0}, expected one of: {}",
+        crate::export::Column::names()
+    )]
+    UnknownColumn(String),
+    #[cfg(feature = "polars")]
+    #[error("Building the data frame failed")]
+    DataFrameError(#[from] polars::prelude::PolarsError),
+}
+
+#[cfg(not(feature = "mmap"))]
+pub fn parse_file<P>(filename: P) -> Result<Vec<Entry>, ParseError>
+where
+    P: AsRef<Path>,
+{
+    let file = open_file(filename.as_ref())?;
+    parse_buffered(BufReader::new(file), true, Grammar::default())
 }
 
+/// Memory-maps the file and parses the mapped bytes directly, falling back to
+/// buffered reading when the file cannot be mapped.
+#[cfg(feature = "mmap")]
 pub fn parse_file<P>(filename: P) -> Result<Vec<Entry>, ParseError>
 where
     P: AsRef<Path>,
 {
-    let file = File::open(filename).map_err(ParseError::FileReadError)?;
-    parse_lines(BufReader::new(file).lines())
+    let file = open_file(filename.as_ref())?;
+    // SAFETY: the map is only read for the duration of this call. Concurrent
+    // truncation of the file by another process is the usual mmap caveat.
+    match unsafe { memmap2::Mmap::map(&file) } {
+        Ok(mmap) => parse_mapped(&mmap),
+        Err(_) => parse_buffered(BufReader::new(file), true, Grammar::default()),
+    }
+}
+
+#[cfg(feature = "mmap")]
+fn parse_mapped(bytes: &[u8]) -> Result<Vec<Entry>, ParseError> {
+    let text = decode_utf8(bytes)?;
+    let base = text.as_ptr() as usize;
+    let lines = text
+        .lines()
+        .map(|line| ((line.as_ptr() as usize - base) as u64, line));
+    parse_lines(lines, true, Grammar::default())
+}
+
+/// Like [`parse_file`], for async callers: the file is read with
+/// `tokio::fs` and parsed on the blocking thread pool, so neither the I/O nor
+/// the parsing holds up the executor. The file is never memory-mapped. A
+/// parse cancelled with the runtime shutting down is reported as an
+/// [`io::ErrorKind::Interrupted`] read error, while a panic in it is resumed.
+#[cfg(feature = "tokio")]
+pub async fn parse_file_async<P: AsRef<Path>>(path: P) -> Result<Vec<Entry>, ParseError> {
+    let path = path.as_ref();
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|source| ParseError::FileOpen {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    tokio::task::spawn_blocking(move || parse_buffered(bytes.as_slice(), true, Grammar::default()))
+        .await
+        .unwrap_or_else(|err| {
+            if err.is_panic() {
+                std::panic::resume_unwind(err.into_panic());
+            }
+            Err(io::Error::new(io::ErrorKind::Interrupted, err).into())
+        })
+}
+
+/// Opens `path`, keeping it in the error so multi-file callers can tell which
+/// file failed.
+pub fn open_file(path: &Path) -> Result<File, ParseError> {
+    File::open(path).map_err(|source| ParseError::FileOpen {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+pub fn parse_str(s: &str) -> Result<Vec<Entry>, ParseError> {
+    parse_reader(s.as_bytes())
+}
+
+/// Parses entries from bytes already in memory. Invalid UTF-8 is an error;
+/// use [`parse_bytes_lossy`] to replace it instead.
+pub fn parse_bytes(bytes: &[u8]) -> Result<Vec<Entry>, ParseError> {
+    parse_str(decode_utf8(bytes)?)
+}
+
+/// Like [`parse_bytes`], but invalid UTF-8 sequences become U+FFFD.
+pub fn parse_bytes_lossy(bytes: &[u8]) -> Result<Vec<Entry>, ParseError> {
+    parse_str(&String::from_utf8_lossy(bytes))
+}
+
+fn decode_utf8(bytes: &[u8]) -> Result<&str, ParseError> {
+    std::str::from_utf8(bytes).map_err(|err| ParseError::InvalidUtf8 {
+        valid_up_to: err.valid_up_to(),
+    })
 }
 
-fn parse_lines(lines: Lines<BufReader<File>>) -> Result<Vec<Entry>, ParseError> {
-    const SEPARATOR: &str = "==========";
+/// Parses entries from any reader. Entries carry no source offset since the
+/// reader may not be seekable.
+pub fn parse_reader<R: BufRead>(reader: R) -> Result<Vec<Entry>, ParseError> {
+    parse_buffered(reader, false, Grammar::default())
+}
+
+/// Outcome of a lenient parse: every entry that parsed, plus the failures
+/// paired with the line number where the offending block starts and the raw
+/// lines of that block. Entries that parsed but lost precision are listed in
+/// `warnings`, also by line number.
+#[derive(Debug, Default)]
+pub struct ParseReport {
+    pub entries: Vec<Entry>,
+    pub errors: Vec<(usize, ParseError, Vec<String>)>,
+    pub warnings: Vec<(usize, ParseWarning)>,
+    /// Whether the entries were read with a custom [`MetaRegex`].
+    pub(crate) custom_meta: bool,
+}
+
+impl ParseReport {
+    /// How many entries were written in each variant of the format that
+    /// differs from the usual `- Your Highlight on page 5 | Location 100-102 |
+    /// Added on ...`, to see which quirks a device's export has. An entry
+    /// counts once for every variant it uses:
+    ///
+    /// - `my-prefix`: `- My Highlight` instead of `- Your Highlight`
+    /// - `no-prefix`: `- Highlight`, with neither
+    /// - `clipping-kind`: `Clipping` written for a highlight
+    /// - `location-colon`: `Location: 100-102`
+    /// - `unicode-dash`: a dash other than `-` in the location range
+    /// - `page-only`: a page but no location
+    /// - `translated-date-label`: the date not preceded by `Added on`
+    /// - `date-only`: a date without a time of day
+    ///
+    /// Variants no entry uses are absent. These describe the built-in
+    /// grammar only, so after a parse with a custom [`MetaRegex`] the tally
+    /// is unavailable and this returns `None`.
+    pub fn format_variants(&self) -> Option<HashMap<&'static str, usize>> {
+        if self.custom_meta {
+            return None;
+        }
+        let mut counts = HashMap::new();
+        for entry in &self.entries {
+            for variant in format_variants(entry) {
+                *counts.entry(variant).or_insert(0) += 1;
+            }
+        }
+        Some(counts)
+    }
+}
+
+fn format_variants(entry: &Entry) -> Vec<&'static str> {
+    let Some(meta) = entry.raw_meta() else {
+        return vec![];
+    };
+    let mut variants = vec![];
+    if meta.starts_with("- My ") {
+        variants.push("my-prefix");
+    } else if !meta.starts_with("- Your ") {
+        variants.push("no-prefix");
+    }
+    let kind = meta
+        .split_whitespace()
+        .find(|word| !matches!(*word, "-" | "Your" | "My"));
+    if kind == Some("Clipping") {
+        variants.push("clipping-kind");
+    }
+    if meta.contains("Location:") {
+        variants.push("location-colon");
+    }
+    if entry.location.is_some()
+        && meta.contains(|c| matches!(c, '\u{2010}'..='\u{2014}' | '\u{2212}'))
+    {
+        variants.push("unicode-dash");
+    }
+    if entry.location.is_none() && entry.page.is_some() {
+        variants.push("page-only");
+    }
+    let date = meta.rsplit('|').next().unwrap_or(meta).trim();
+    if !date.starts_with("Added on ") {
+        variants.push("translated-date-label");
+    }
+    if entry.date_precision == DatePrecision::Date {
+        variants.push("date-only");
+    }
+    variants
+}
+
+/// Something about an entry that parsed but may not be what it seems.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseWarning {
+    #[error("Date has no time of day, assuming midnight")]
+    DateWithoutTime,
+}
+
+pub fn parse_file_lenient<P>(filename: P) -> Result<ParseReport, ParseError>
+where
+    P: AsRef<Path>,
+{
+    let file = open_file(filename.as_ref())?;
+    Ok(parse_buffered_lenient(
+        BufReader::new(file),
+        true,
+        Grammar::default(),
+    ))
+}
+
+pub fn parse_reader_lenient<R: BufRead>(reader: R) -> ParseReport {
+    parse_buffered_lenient(reader, false, Grammar::default())
+}
+
+/// Parses one entry from the lines between two separators, without the
+/// separators themselves:
+///
+/// ```text
+/// Title (Author)
+/// - Your Highlight on page 5 | Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+///
+/// Text, possibly spanning several lines.
+/// ```
+///
+/// The blank line after the metadata is optional. This accepts the raw lines
+/// kept in [`ParseReport::errors`], so a repaired block can be checked on its
+/// own.
+pub fn parse_block<S: AsRef<str>>(lines: &[S]) -> Result<Entry, ParseError> {
+    parse_entry(lines, Grammar::default())
+}
+
+/// Parses entries lazily as they are read, so memory use does not grow with
+/// the input. A read error ends the iteration after being yielded once, in
+/// place of the block it cut short.
+pub fn parse_reader_iter<R: BufRead>(reader: R) -> EntryIter<R> {
+    EntryIter {
+        lines: OffsetLines::new(reader),
+        done: false,
+    }
+}
+
+/// Number of entries in the reader, checking that each one parses but
+/// without keeping them. Stops at the first error.
+pub fn count_entries<R: BufRead>(reader: R) -> Result<usize, ParseError> {
+    parse_reader_iter(reader).try_fold(0, |count, entry| entry.map(|_| count + 1))
+}
+
+/// Number of blocks between separators that are not only whitespace,
+/// without parsing them: much faster than [`count_entries`], but malformed
+/// blocks are counted too.
+pub fn count_blocks<R: BufRead>(reader: R) -> Result<usize, ParseError> {
+    let mut lines = OffsetLines::new(reader);
+    let mut count = 0;
+    let mut in_block = false;
+    for (_, line) in &mut lines {
+        if line == SEPARATOR {
+            count += usize::from(in_block);
+            in_block = false;
+        } else if !line.trim().is_empty() {
+            in_block = true;
+        }
+    }
+    lines.finish()?;
+    Ok(count + usize::from(in_block))
+}
+
+/// Iterator returned by [`parse_reader_iter`].
+pub struct EntryIter<R> {
+    lines: OffsetLines<R>,
+    done: bool,
+}
+
+impl<R: BufRead> Iterator for EntryIter<R> {
+    type Item = Result<Entry, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let mut block: Option<Block<String>> = None;
+            loop {
+                match self.lines.next() {
+                    Some((_, line)) if line == SEPARATOR => break,
+                    Some((offset, line)) => {
+                        let number = self.lines.line;
+                        block
+                            .get_or_insert_with(|| Block {
+                                line: number,
+                                offset,
+                                lines: vec![],
+                            })
+                            .lines
+                            .push(line);
+                    }
+                    None => {
+                        self.done = true;
+                        if let Some(err) = self.lines.error.take() {
+                            return Some(Err(err));
+                        }
+                        break;
+                    }
+                }
+            }
+            match block {
+                Some(block) if !block.skip_if_empty() => {
+                    return Some(block.parse(false, Grammar::default()))
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// A read error takes precedence over the parse error it likely caused by
+/// cutting the last block short.
+pub(crate) fn parse_buffered<R: BufRead>(
+    reader: R,
+    track_offsets: bool,
+    grammar: Grammar<'_>,
+) -> Result<Vec<Entry>, ParseError> {
+    let mut lines = OffsetLines::new(reader);
+    let parsed = parse_lines(&mut lines, track_offsets, grammar);
+    lines.finish()?;
+    parsed
+}
+
+pub(crate) fn parse_buffered_lenient<R: BufRead>(
+    reader: R,
+    track_offsets: bool,
+    grammar: Grammar<'_>,
+) -> ParseReport {
+    let mut lines = OffsetLines::new(reader);
+    let mut report = parse_lines_lenient(&mut lines, track_offsets, grammar);
+    let line = lines.line + 1;
+    if let Err(err) = lines.finish() {
+        report.errors.push((line, err, vec![]));
+    }
+    report
+}
+
+fn parse_lines<S, I>(
+    lines: I,
+    track_offsets: bool,
+    grammar: Grammar<'_>,
+) -> Result<Vec<Entry>, ParseError>
+where
+    S: AsRef<str>,
+    I: Iterator<Item = (u64, S)>,
+{
+    blocks(lines)
+        .into_iter()
+        .map(|block| block.parse(track_offsets, grammar))
+        .collect()
+}
+
+fn parse_lines_lenient<I>(lines: I, track_offsets: bool, grammar: Grammar<'_>) -> ParseReport
+where
+    I: Iterator<Item = (u64, String)>,
+{
+    let mut report = ParseReport::default();
+    for block in blocks(lines) {
+        match block.parse(track_offsets, grammar) {
+            Ok(entry) => {
+                if entry.date_precision == DatePrecision::Date {
+                    report
+                        .warnings
+                        .push((block.line, ParseWarning::DateWithoutTime));
+                }
+                report.entries.push(entry)
+            }
+            Err(err) => report.errors.push((block.line, err, block.lines)),
+        }
+    }
+    report
+}
+
+/// Lines of a reader paired with the byte offset where each one starts, with
+/// the line terminator removed. Reading stops at the first I/O error or
+/// invalid UTF-8, which is kept for [`OffsetLines::finish`]. Invalid UTF-8 is
+/// reported as [`ParseError::InvalidUtf8`], the same as for a mapped file.
+struct OffsetLines<R> {
+    reader: R,
+    offset: u64,
+    line: usize,
+    error: Option<ParseError>,
+}
+
+impl<R: BufRead> OffsetLines<R> {
+    fn new(reader: R) -> Self {
+        OffsetLines {
+            reader,
+            offset: 0,
+            line: 0,
+            error: None,
+        }
+    }
+
+    fn finish(self) -> Result<(), ParseError> {
+        self.error.map_or(Ok(()), Err)
+    }
+}
+
+impl<R: BufRead> Iterator for OffsetLines<R> {
+    type Item = (u64, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        let mut line = vec![];
+        let line = match self.reader.read_until(b'\n', &mut line) {
+            Ok(0) => return None,
+            Err(err) => Err(err.into()),
+            Ok(_) => String::from_utf8(line).map_err(|err| ParseError::InvalidUtf8 {
+                valid_up_to: self.offset as usize + err.utf8_error().valid_up_to(),
+            }),
+        };
+        match line {
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+            Ok(mut line) => {
+                let read = line.len();
+                let offset = self.offset;
+                self.offset += read as u64;
+                self.line += 1;
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+                Some((offset, line))
+            }
+        }
+    }
+}
+
+/// Lines between two separators, with the 1-based line number and the byte
+/// offset where the block starts.
+struct Block<S> {
+    line: usize,
+    offset: u64,
+    lines: Vec<S>,
+}
+
+impl<S: AsRef<str>> Block<S> {
+    fn parse(&self, track_offset: bool, grammar: Grammar<'_>) -> Result<Entry, ParseError> {
+        let mut entry = parse_entry(&self.lines, grammar)?;
+        if track_offset {
+            entry.source_offset = Some(self.offset);
+        }
+        self.warn_if_suspicious(&entry);
+        Ok(entry)
+    }
+
+    /// Whether the block is only whitespace, logging that it is skipped.
+    fn skip_if_empty(&self) -> bool {
+        let empty = self
+            .lines
+            .iter()
+            .all(|line| line.as_ref().trim().is_empty());
+        if empty {
+            log::warn!("line {}: skipping empty block", self.line);
+        }
+        empty
+    }
+
+    /// Entries that parse but are probably wrong, such as a clock that was
+    /// off when the highlight was made, are reported through `log`.
+    fn warn_if_suspicious(&self, entry: &Entry) {
+        /// Wider than any highlight Kindle lets you make.
+        const MAX_LOCATION_SPAN: u64 = 1000;
+
+        if entry.creation_date > Local::now().naive_local() {
+            log::warn!(
+                "line {}: entry is dated in the future ({})",
+                self.line,
+                entry.creation_date
+            );
+        }
+        let location = entry.position();
+        if location.end() - location.start() > MAX_LOCATION_SPAN {
+            log::warn!(
+                "line {}: location {}-{} spans more than {} locations",
+                self.line,
+                location.start(),
+                location.end(),
+                MAX_LOCATION_SPAN
+            );
+        }
+    }
+}
+
+/// The line between two entries.
+pub(crate) const SEPARATOR: &str = "==========";
+
+/// Splits the lines on the separator, dropping blocks made only of whitespace.
+fn blocks<S, I>(lines: I) -> Vec<Block<S>>
+where
+    S: AsRef<str>,
+    I: Iterator<Item = (u64, S)>,
+{
+    // Back-to-back separators fall into one group, and blank lines between
+    // them form a whitespace-only block that is dropped below.
     lines
-        .flatten()
-        .group_by(|line| line != SEPARATOR)
+        .enumerate()
+        .group_by(|(_, (_, line))| line.as_ref() != SEPARATOR)
         .into_iter()
         .filter(|(id, _)| *id)
-        .map(|(_, group)| parse_entry(group.collect()))
+        .map(|(_, mut group)| {
+            let (index, (offset, first)) = group.next().expect("groups are never empty");
+            let mut lines = vec![first];
+            lines.extend(group.map(|(_, (_, line))| line));
+            Block {
+                line: index + 1,
+                offset,
+                lines,
+            }
+        })
+        .filter(|block| !block.skip_if_empty())
         .collect()
 }
 
-fn parse_entry(lines: Vec<String>) -> Result<Entry, ParseError> {
-    let title_author_regex = Regex::new(r"^(.*) \((.*)\)$").unwrap();
-    let first_line_captures = title_author_regex.captures(lines[0].as_str()).unwrap();
+/// Parses the part of the metadata line after the last `|`. The label in front
+/// of the date ("Added on", or its translation on mixed-locale devices) is
+/// skipped by trying successively shorter word suffixes.
+///
+/// Dates without a time of day, which a few exports write, fall back to
+/// midnight and are marked [`DatePrecision::Date`].
+fn parse_date(value: &str) -> Result<(NaiveDateTime, DatePrecision), ParseError> {
+    const DATE_ONLY_FORMATS: [&str; 2] = ["%A, %B %-e, %Y", "%B %-e, %Y"];
+    let without_label = value.strip_prefix("Added on ").unwrap_or(value);
+    let candidates = || {
+        std::iter::once(without_label).chain(
+            without_label
+                .match_indices(char::is_whitespace)
+                .map(|(index, _)| without_label[index..].trim_start()),
+        )
+    };
+    candidates()
+        .find_map(|candidate| NaiveDateTime::parse_from_str(candidate, KINDLE_DATE_FORMAT).ok())
+        .map(|date| (date, DatePrecision::DateTime))
+        .or_else(|| {
+            candidates()
+                .find_map(|candidate| {
+                    DATE_ONLY_FORMATS
+                        .iter()
+                        .find_map(|format| NaiveDate::parse_from_str(candidate, format).ok())
+                })
+                .map(|date| (date.and_time(NaiveTime::MIN), DatePrecision::Date))
+        })
+        .ok_or_else(|| ParseError::InvalidDate(value.to_string()))
+}
+
+/// Everything after the metadata lines, skipping the blank line that
+/// normally separates them from the body. Multi-line bodies are joined with
+/// `\n` and trailing blank lines are dropped. Indentation shared by every
+/// line, which some sync tools add, is removed; deeper indentation stays.
+fn parse_text<S: AsRef<str>>(body: &[S]) -> Result<String, ParseError> {
+    let body = match body.first() {
+        Some(first) if first.as_ref().trim().is_empty() => &body[1..],
+        _ => body,
+    };
+    if body.is_empty() {
+        return Err(ParseError::TextNotFound);
+    }
+    let end = body
+        .iter()
+        .rposition(|line| !line.as_ref().trim().is_empty())
+        .map_or(0, |last| last + 1);
+    let body = &body[..end];
+    let indent = body
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .reduce(common_prefix)
+        .unwrap_or("");
+    Ok(body
+        .iter()
+        .map(|line| {
+            let line = line.as_ref();
+            line.strip_prefix(indent).unwrap_or(line)
+        })
+        .join("\n"))
+}
+
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let len = a
+        .chars()
+        .zip(b.chars())
+        .take_while(|(x, y)| x == y)
+        .map(|(c, _)| c.len_utf8())
+        .sum();
+    &a[..len]
+}
 
-    let kind_page_location_date_regex =
-        Regex::new(r"^- Your (.*) on( page ([0-9]+) \|)? Location ([0-9\-]+) \| Added on (.*)$")
-            .unwrap();
-    let second_line_captures = kind_page_location_date_regex
-        .captures(lines[1].as_str())
-        .unwrap();
+/// The grammar of an entry's second line, which names what the entry is,
+/// where it is and when it was made. [`MetaRegex::from_str`] takes a
+/// replacement for exports the built-in one does not understand; it must
+/// have the named groups `kind`, `page`, `location` and `date`, and may have
+/// `color`. A group that does not take part in a match counts as absent,
+/// and an entry needs a `location` or a `page`:
+///
+/// ```
+/// use clippings_parser::{MetaRegex, ParseOptions};
+///
+/// let meta: MetaRegex = r"^\* (?P<kind>\w+)( p\.(?P<page>\d+))?( loc\.(?P<location>[\d-]+))? @ (?P<date>.*)$"
+///     .parse()
+///     .unwrap();
+/// let report = ParseOptions::new().meta_regex(meta).parse_reader("\
+/// Dune (Frank Herbert)
+/// * Highlight loc.10-11 @ Monday, February 7, 2022 9:00:00 PM
+///
+/// Fear is the mind-killer.
+/// ==========
+/// ".as_bytes()).unwrap();
+/// assert_eq!(report.entries[0].position().start(), 10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetaRegex(Regex);
 
-    let title = match first_line_captures.get(1) {
-        Some(value) => Ok(value.as_str().to_string()),
-        None => Err(ParseError::TitleNotFound),
-    }?;
+impl MetaRegex {
+    const GROUPS: [&'static str; 4] = ["kind", "page", "location", "date"];
 
-    let author = match first_line_captures.get(2) {
-        Some(value) => Ok(value.as_str().to_string()),
-        None => Err(ParseError::AuthorNotFound),
-    }?;
+    pub(crate) fn builtin() -> &'static MetaRegex {
+        static BUILTIN: OnceLock<MetaRegex> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            MetaRegex(
+                Regex::new(concat!(
+                    r"^- ((Your|My) )?(?P<kind>\S+)( \((?P<color>[^)]*)\))? on",
+                    r"( page (?P<page>[0-9]+) \|)?(\s*Location:?\s*(?P<location>[0-9\-\x{2010}-\x{2014}\x{2212}]+)\s*\|)?",
+                    r"(?P<date>[^|]*)$"
+                ))
+                .unwrap(),
+            )
+        })
+    }
+}
 
-    let kind = match second_line_captures.get(1) {
+impl FromStr for MetaRegex {
+    type Err = ParseError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let regex =
+            Regex::new(pattern).map_err(|err| ParseError::InvalidMetaRegex(err.to_string()))?;
+        let names: Vec<&str> = regex.capture_names().flatten().collect();
+        match MetaRegex::GROUPS
+            .iter()
+            .find(|group| !names.contains(group))
+        {
+            Some(group) => Err(ParseError::InvalidMetaRegex(format!(
+                "missing the named group `{}`",
+                group
+            ))),
+            None => Ok(MetaRegex(regex)),
+        }
+    }
+}
+
+/// How the first two lines of an entry are read, fixed for a whole parse.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Grammar<'a> {
+    pub(crate) meta: &'a MetaRegex,
+    /// The whole first line is the title and the author is left empty.
+    pub(crate) no_author: bool,
+}
+
+impl Default for Grammar<'static> {
+    fn default() -> Self {
+        Grammar {
+            meta: MetaRegex::builtin(),
+            no_author: false,
+        }
+    }
+}
+
+fn parse_entry<S: AsRef<str>>(lines: &[S], grammar: Grammar<'_>) -> Result<Entry, ParseError> {
+    static TITLE_AUTHOR_REGEX: OnceLock<Regex> = OnceLock::new();
+
+    let title_author_regex =
+        TITLE_AUTHOR_REGEX.get_or_init(|| Regex::new(r"^(.*) \((.*)\)\p{Bidi_Control}*$").unwrap());
+    let (first_line, second_line) = match lines {
+        [first, second, ..] => (first.as_ref(), second.as_ref()),
+        [first] => return Err(ParseError::MalformedEntry(first.as_ref().to_string())),
+        [] => return Err(ParseError::MalformedEntry(String::new())),
+    };
+    let first_line_captures = if grammar.no_author {
+        None
+    } else {
+        Some(
+            title_author_regex
+                .captures(first_line)
+                .ok_or_else(|| ParseError::MalformedEntry(first_line.to_string()))?,
+        )
+    };
+
+    let second_line_captures = grammar
+        .meta
+        .0
+        .captures(second_line)
+        .ok_or_else(|| ParseError::MalformedEntry(second_line.to_string()))?;
+
+    let (title, author) = match first_line_captures {
+        None => (first_line.trim().to_string(), String::new()),
+        Some(first_line_captures) => {
+            // Stray spaces around the parenthesized author would otherwise
+            // split one book into several when grouping.
+            let title = match first_line_captures.get(1) {
+                Some(value) => Ok(value.as_str().trim().to_string()),
+                None => Err(ParseError::TitleNotFound),
+            }?;
+
+            let author = match first_line_captures.get(2) {
+                Some(value) => Ok(value.as_str().trim().to_string()),
+                None => Err(ParseError::AuthorNotFound),
+            }?;
+            (title, author)
+        }
+    };
+
+    let kind = match second_line_captures.name("kind") {
         Some(value) => EntryType::from_str(value.as_str()),
         None => Err(ParseError::KindNotFound),
     }?;
 
-    let page: Option<Page> = match second_line_captures.get(3) {
+    let color = second_line_captures
+        .name("color")
+        .map(|value| value.as_str().to_string());
+
+    let page: Option<Page> = match second_line_captures.name("page") {
         Some(value) => Some(Page::from_str(value.as_str())?),
         None => None,
     };
 
-    let location = match second_line_captures.get(4) {
-        Some(value) => Location::from_str(value.as_str()),
-        None => Err(ParseError::LocationNotFound),
-    }?;
+    // Fixed-layout books only have a page.
+    let location = match second_line_captures.name("location") {
+        Some(value) => Some(Location::from_str(value.as_str())?),
+        None if page.is_some() => None,
+        None => return Err(ParseError::LocationNotFound),
+    };
 
-    let date = match second_line_captures.get(5) {
-        Some(value) => NaiveDateTime::parse_from_str(value.as_str(), "%A, %B %-e, %Y %-l:%M:%S %p")
-            .map_err(|_| ParseError::InvalidDate(value.as_str().to_string())),
+    let (date, date_precision) = match second_line_captures.name("date") {
+        Some(value) => parse_date(value.as_str().trim()),
         None => Err(ParseError::DateNotFound),
     }?;
 
-    let text = lines[3].to_string();
+    let text = parse_text(&lines[2..])?;
 
     Ok(Entry {
         title,
         author,
         kind,
+        color,
         page,
         location,
         creation_date: date,
+        date_precision,
         text,
+        source_offset: None,
+        raw_meta: Some(second_line.to_string()),
     })
 }
@@ -0,0 +1,383 @@
+use crate::file_parser::{Entry, EntryType, Location};
+use chrono::NaiveDate;
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::{HashMap, HashSet};
+
+/// Lazy counterparts of the filters below for iterators over borrowed
+/// entries, so they can be chained without collecting in between:
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use clippings_parser::filters::EntryIterExt;
+/// # let entries: Vec<clippings_parser::Entry> = vec![];
+///
+/// let since = NaiveDate::from_ymd_opt(2023, 1, 1);
+/// let recent: Vec<_> = entries
+///     .iter()
+///     .highlights()
+///     .by_author("herbert")
+///     .in_range(since, None)
+///     .collect();
+/// ```
+pub trait EntryIterExt<'a>: Iterator<Item = &'a Entry> + Sized {
+    fn of_kind(self, kind: EntryType) -> impl Iterator<Item = &'a Entry> {
+        self.filter(move |entry| *entry.kind() == kind)
+    }
+
+    fn highlights(self) -> impl Iterator<Item = &'a Entry> {
+        self.of_kind(EntryType::Highlight)
+    }
+
+    /// See [`filter_by_author`].
+    fn by_author(self, author: &str) -> impl Iterator<Item = &'a Entry> {
+        let needle = author.to_lowercase();
+        self.filter(move |entry| author_matches(entry, &needle))
+    }
+
+    /// See [`filter_by_date`].
+    fn in_range(
+        self,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> impl Iterator<Item = &'a Entry> {
+        self.filter(move |entry| in_range(entry, since, until))
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a Entry>> EntryIterExt<'a> for I {}
+
+/// Removes Unicode bidirectional control characters (LRM, RLM, embeddings,
+/// isolates) from titles and authors. The parser keeps names byte-for-byte,
+/// so this is only applied when explicitly requested.
+pub fn strip_bidi_controls(entries: Vec<Entry>) -> Vec<Entry> {
+    entries
+        .into_iter()
+        .map(|mut entry| {
+            entry.title = without_bidi_controls(&entry.title);
+            entry.author = without_bidi_controls(&entry.author);
+            entry
+        })
+        .collect()
+}
+
+fn without_bidi_controls(s: &str) -> String {
+    s.chars()
+        .filter(|c| {
+            !matches!(
+                c,
+                '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+            )
+        })
+        .collect()
+}
+
+/// Replaces the text of highlights and notes with `[redacted: N chars]`, N
+/// counting characters, so entries can be shared without the quotes. All
+/// other fields are kept; bookmarks have no text to hide.
+pub fn redact_text(entries: Vec<Entry>) -> Vec<Entry> {
+    entries
+        .into_iter()
+        .map(|mut entry| {
+            if *entry.kind() != EntryType::Bookmark {
+                entry.text = format!("[redacted: {} chars]", entry.text.chars().count());
+            }
+            entry
+        })
+        .collect()
+}
+
+/// Keeps only the most recently created version of entries that share a
+/// title, author, kind and location, which is what Kindle leaves behind when
+/// a highlight is edited. Survivors keep their original order.
+pub fn keep_latest(entries: Vec<Entry>) -> Vec<Entry> {
+    let mut latest: HashMap<(&str, &str, EntryType, Location), usize> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let key = (
+            entry.title(),
+            entry.author(),
+            *entry.kind(),
+            entry.position(),
+        );
+        latest
+            .entry(key)
+            .and_modify(|best| {
+                if entry.creation_date() >= entries[*best].creation_date() {
+                    *best = index;
+                }
+            })
+            .or_insert(index);
+    }
+    let mut keep = vec![false; entries.len()];
+    for index in latest.into_values() {
+        keep[index] = true;
+    }
+    entries
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(entry, keep)| keep.then_some(entry))
+        .collect()
+}
+
+/// Keeps one highlight per title and location start, for exports where
+/// Kindle repeated a highlight with slightly different selections: the one
+/// with the longest text, or of those the latest. Unlike [`keep_latest`],
+/// the author and the end of the location do not have to match. Other kinds
+/// are kept as they are, and survivors keep their original order.
+pub fn collapse_by_location(entries: Vec<Entry>) -> Vec<Entry> {
+    fn preference(entry: &Entry) -> (usize, chrono::NaiveDateTime) {
+        (entry.text().chars().count(), entry.creation_date())
+    }
+    let mut best: HashMap<(&str, u64), usize> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        if *entry.kind() != EntryType::Highlight {
+            continue;
+        }
+        best.entry((entry.title(), entry.position().start()))
+            .and_modify(|kept| {
+                if preference(entry) > preference(&entries[*kept]) {
+                    *kept = index;
+                }
+            })
+            .or_insert(index);
+    }
+    let mut keep: Vec<bool> = entries
+        .iter()
+        .map(|entry| *entry.kind() != EntryType::Highlight)
+        .collect();
+    for index in best.into_values() {
+        keep[index] = true;
+    }
+    entries
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(entry, keep)| keep.then_some(entry))
+        .collect()
+}
+
+/// Drops exact duplicates, such as the same clipping read from two devices,
+/// keeping the first occurrence. See [`Entry`]'s `PartialEq` for what counts
+/// as the same.
+pub fn dedup(entries: Vec<Entry>) -> Vec<Entry> {
+    let mut seen = HashSet::new();
+    entries
+        .into_iter()
+        .filter(|entry| seen.insert(entry.clone()))
+        .collect()
+}
+
+/// Like [`dedup`], but titles and authors that differ only in case or
+/// spacing, such as `The Odyssey` and `THE  ODYSSEY`, count as the same.
+/// The kept entry shows the title and author of the first occurrence.
+pub fn dedup_normalized(entries: Vec<Entry>) -> Vec<Entry> {
+    fn normalize(value: &str) -> String {
+        value.split_whitespace().join(" ").to_lowercase()
+    }
+    let mut seen = HashSet::new();
+    entries
+        .into_iter()
+        .filter(|entry| {
+            let (title, author, kind, color, page, location, date, text) = entry.content();
+            seen.insert((
+                normalize(title),
+                normalize(author),
+                kind,
+                color.map(str::to_string),
+                page,
+                location,
+                date,
+                text.to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Near-duplicate removal for highlights Kindle recorded twice at the same
+/// location, once with and once without a trailing period or space: texts
+/// that only differ in trailing punctuation and whitespace count as the same,
+/// and the longer text is kept in place of the first occurrence.
+pub fn dedup_fuzzy_text(entries: Vec<Entry>) -> Vec<Entry> {
+    let mut kept: Vec<Entry> = Vec::with_capacity(entries.len());
+    let mut seen: HashMap<_, usize> = HashMap::new();
+    for entry in entries {
+        let key = (
+            entry.title().to_string(),
+            entry.author().to_string(),
+            *entry.kind(),
+            entry.position(),
+            entry
+                .text()
+                .trim_end_matches(|c: char| !c.is_alphanumeric())
+                .to_string(),
+        );
+        match seen.get(&key) {
+            Some(&index) => {
+                if entry.text().chars().count() > kept[index].text().chars().count() {
+                    kept[index] = entry;
+                }
+            }
+            None => {
+                seen.insert(key, kept.len());
+                kept.push(entry);
+            }
+        }
+    }
+    kept
+}
+
+/// Same result as [`dedup`], without hashing: entries are sorted by every
+/// field that counts for equality, starting with title and author, and
+/// adjacent duplicates are removed. The input order is lost, which
+/// [`dedup`] keeps.
+pub fn sort_dedup(mut entries: Vec<Entry>) -> Vec<Entry> {
+    entries.sort_by(|a, b| a.content().cmp(&b.content()));
+    entries.dedup();
+    entries
+}
+
+/// What changed between two sets of entries, such as two exports of the same
+/// device, compared with [`Entry`]'s `PartialEq`.
+#[derive(Debug, Clone, Default)]
+pub struct Diff<'a> {
+    /// Entries of `new` that are not in `old`, in the order of `new`.
+    pub added: Vec<&'a Entry>,
+    /// Entries of `old` that are not in `new`, in the order of `old`.
+    pub removed: Vec<&'a Entry>,
+}
+
+/// Entries only in `new` and only in `old`. Duplicates within one side are
+/// listed once.
+pub fn diff<'a>(old: &'a [Entry], new: &'a [Entry]) -> Diff<'a> {
+    let old_set: HashSet<&Entry> = old.iter().collect();
+    let new_set: HashSet<&Entry> = new.iter().collect();
+    let only = |entries: &'a [Entry], other: &HashSet<&Entry>| {
+        let mut seen = HashSet::new();
+        entries
+            .iter()
+            .filter(|entry| !other.contains(entry) && seen.insert(*entry))
+            .collect()
+    };
+    Diff {
+        added: only(new, &old_set),
+        removed: only(old, &new_set),
+    }
+}
+
+/// Keeps entries whose author contains `author`, ignoring case.
+pub fn filter_by_author(entries: Vec<Entry>, author: &str) -> Vec<Entry> {
+    let needle = author.to_lowercase();
+    entries
+        .into_iter()
+        .filter(|entry| author_matches(entry, &needle))
+        .collect()
+}
+
+fn author_matches(entry: &Entry, needle: &str) -> bool {
+    entry.author().to_lowercase().contains(needle)
+}
+
+/// Keeps entries of the book titled exactly `title`, by any author.
+pub fn filter_by_book(entries: Vec<Entry>, title: &str) -> Vec<Entry> {
+    entries
+        .into_iter()
+        .filter(|entry| entry.title() == title)
+        .collect()
+}
+
+/// Keeps entries whose title contains `title`, ignoring case.
+pub fn filter_by_book_contains(entries: Vec<Entry>, title: &str) -> Vec<Entry> {
+    let needle = title.to_lowercase();
+    entries
+        .into_iter()
+        .filter(|entry| entry.title().to_lowercase().contains(&needle))
+        .collect()
+}
+
+pub fn filter_by_kind(entries: Vec<Entry>, kind: EntryType) -> Vec<Entry> {
+    entries
+        .into_iter()
+        .filter(|entry| *entry.kind() == kind)
+        .collect()
+}
+
+/// Keeps entries created between `since` and `until`, both inclusive. A
+/// missing bound leaves that side open.
+pub fn filter_by_date(
+    entries: Vec<Entry>,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> Vec<Entry> {
+    entries
+        .into_iter()
+        .filter(|entry| in_range(entry, since, until))
+        .collect()
+}
+
+fn in_range(entry: &Entry, since: Option<NaiveDate>, until: Option<NaiveDate>) -> bool {
+    let date = entry.creation_date().date();
+    since.is_none_or(|since| date >= since) && until.is_none_or(|until| date <= until)
+}
+
+/// Entries whose location overlaps `range`, such as the highlights of one
+/// chapter. Entries without a location, from page-only books, are left out.
+pub fn filter_by_location<'a>(entries: &'a [Entry], range: &Location) -> Vec<&'a Entry> {
+    entries
+        .iter()
+        .filter(|entry| {
+            entry
+                .location()
+                .is_some_and(|location| location.overlaps(range))
+        })
+        .collect()
+}
+
+/// Drops highlights whose trimmed text has fewer than `min` characters
+/// (Unicode scalar values, not bytes). Notes and bookmarks are kept.
+pub fn filter_min_length(entries: Vec<Entry>, min: usize) -> Vec<Entry> {
+    entries
+        .into_iter()
+        .filter(|entry| *entry.kind() != EntryType::Highlight || long_enough(entry, min))
+        .collect()
+}
+
+/// Like [`filter_min_length`] but applies to every kind of entry, so
+/// bookmarks, which have no text, are dropped for any `min` above zero.
+pub fn filter_min_length_all(entries: Vec<Entry>, min: usize) -> Vec<Entry> {
+    entries
+        .into_iter()
+        .filter(|entry| long_enough(entry, min))
+        .collect()
+}
+
+fn long_enough(entry: &Entry, min: usize) -> bool {
+    entry.text().trim().chars().count() >= min
+}
+
+/// Picks `n` highlights uniformly at random, or all of them when there are
+/// fewer. A `seed` makes the choice reproducible. The picked highlights keep
+/// their input order.
+pub fn sample_highlights(entries: Vec<Entry>, n: usize, seed: Option<u64>) -> Vec<Entry> {
+    let highlights: Vec<Entry> = entries
+        .into_iter()
+        .filter(|entry| *entry.kind() == EntryType::Highlight)
+        .collect();
+    if n >= highlights.len() {
+        return highlights;
+    }
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut picked = rand::seq::index::sample(&mut rng, highlights.len(), n).into_vec();
+    picked.sort_unstable();
+    let mut keep = vec![false; highlights.len()];
+    for index in picked {
+        keep[index] = true;
+    }
+    highlights
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(entry, keep)| keep.then_some(entry))
+        .collect()
+}
@@ -0,0 +1,350 @@
+use crate::file_parser::{Entry, EntryType, Location};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Entries of a single book, in the order they appeared in the input.
+#[derive(Debug, Clone)]
+pub struct Book {
+    pub title: String,
+    pub author: String,
+    pub entries: Vec<Entry>,
+}
+
+/// A highlight together with the notes the reader attached to it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Annotation {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub highlight: Entry,
+    pub notes: Vec<Entry>,
+}
+
+/// Groups entries by title and author, ordering the books by title.
+pub fn group_by_book(entries: Vec<Entry>) -> Vec<Book> {
+    let mut books: BTreeMap<(String, String), Vec<Entry>> = BTreeMap::new();
+    for entry in entries {
+        books
+            .entry((entry.title().to_string(), entry.author().to_string()))
+            .or_default()
+            .push(entry);
+    }
+    books
+        .into_iter()
+        .map(|((title, author), entries)| Book {
+            title,
+            author,
+            entries,
+        })
+        .collect()
+}
+
+/// Keeps books with at least `min` highlights; notes and bookmarks do not
+/// count towards the minimum but stay with the books that are kept.
+pub fn filter_books_min(books: Vec<Book>, min: usize) -> Vec<Book> {
+    books
+        .into_iter()
+        .filter(|book| {
+            book.entries
+                .iter()
+                .filter(|entry| *entry.kind() == EntryType::Highlight)
+                .count()
+                >= min
+        })
+        .collect()
+}
+
+/// Groups entries by author, then by book within each author. Authors are
+/// ordered by name and their books by title.
+pub fn group_by_author(entries: Vec<Entry>) -> Vec<(String, Vec<Book>)> {
+    let mut authors: BTreeMap<String, Vec<Book>> = BTreeMap::new();
+    for book in group_by_book(entries) {
+        authors.entry(book.author.clone()).or_default().push(book);
+    }
+    authors.into_iter().collect()
+}
+
+/// Sorts into the canonical order of merged files: by the title of
+/// [`Entry::sort_key`], then author, then the key's position (start, then
+/// end), then creation date, so that each book's entries read front to back
+/// and edits of a highlight follow the original. The sort is stable, so
+/// entries equal on all of these keep their order.
+pub fn sort_by_book_and_location(entries: &mut [Entry]) {
+    entries.sort_by_cached_key(|entry| {
+        let (title, start, end) = entry.sort_key();
+        (
+            title,
+            entry.author().to_string(),
+            start,
+            end,
+            entry.creation_date(),
+        )
+    });
+}
+
+/// How books are ordered in grouped exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BookOrder {
+    #[default]
+    Title,
+    /// By author surname, then title.
+    Author,
+}
+
+pub fn sort_books(books: &mut [Book], order: BookOrder) {
+    match order {
+        BookOrder::Title => books.sort_by(|a, b| (&a.title, &a.author).cmp(&(&b.title, &b.author))),
+        BookOrder::Author => books.sort_by_cached_key(|book| {
+            (
+                normalize_author(&book.author).to_lowercase(),
+                book.title.clone(),
+            )
+        }),
+    }
+}
+
+/// Rewrites `First Last` as `Last, First` so authors sort by surname. Names
+/// that already contain a comma are taken to be in that form.
+pub fn normalize_author(author: &str) -> String {
+    let author = author.trim();
+    if author.contains(',') {
+        return author.to_string();
+    }
+    match author.rsplit_once(char::is_whitespace) {
+        Some((first, last)) => format!("{}, {}", last, first.trim_end()),
+        None => author.to_string(),
+    }
+}
+
+/// Entries kept sorted by book so that each book can be borrowed as a
+/// contiguous slice without copying.
+#[derive(Debug, Clone, Default)]
+pub struct Collection {
+    entries: Vec<Entry>,
+}
+
+/// Borrowed view of one book inside a [`Collection`].
+#[derive(Debug, Clone, Copy)]
+pub struct BookRef<'a> {
+    entries: &'a [Entry],
+}
+
+impl Collection {
+    /// Orders the entries by title and author, keeping the input order within
+    /// each book.
+    pub fn new(mut entries: Vec<Entry>) -> Self {
+        entries.sort_by(|a, b| (a.title(), a.author()).cmp(&(b.title(), b.author())));
+        Collection { entries }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn books(&self) -> impl Iterator<Item = BookRef<'_>> {
+        self.entries
+            .chunk_by(same_book)
+            .map(|entries| BookRef { entries })
+    }
+}
+
+impl From<Vec<Entry>> for Collection {
+    fn from(entries: Vec<Entry>) -> Self {
+        Collection::new(entries)
+    }
+}
+
+impl<'a> BookRef<'a> {
+    pub fn title(&self) -> &'a str {
+        self.entries[0].title()
+    }
+
+    pub fn author(&self) -> &'a str {
+        self.entries[0].author()
+    }
+
+    pub fn entries(&self) -> &'a [Entry] {
+        self.entries
+    }
+}
+
+/// How far from a highlight, in locations, [`attach_notes`] still looks for
+/// the highlight a note belongs to.
+pub const DEFAULT_NOTE_WINDOW: u64 = 2;
+
+/// Pairs every highlight with the notes of the same book, see
+/// [`attach_notes_within`]. Uses [`DEFAULT_NOTE_WINDOW`].
+pub fn attach_notes(entries: Vec<Entry>) -> Vec<Annotation> {
+    attach_notes_within(entries, DEFAULT_NOTE_WINDOW)
+}
+
+/// Pairs every highlight with the notes of the same book. A note goes to the
+/// highlight whose location range contains it, otherwise to the nearest
+/// highlight at most `window` locations away; ties go to the highlight ending
+/// closest to the note. Bookmarks and notes that match no highlight are left
+/// out. Only locations count, not file order, so a note written before its
+/// highlight still finds it.
+pub fn attach_notes_within(entries: Vec<Entry>, window: u64) -> Vec<Annotation> {
+    let (notes, others): (Vec<Entry>, Vec<Entry>) = entries
+        .into_iter()
+        .partition(|entry| matches!(entry.kind(), EntryType::Note));
+    let mut annotations: Vec<Annotation> = others
+        .into_iter()
+        .filter(|entry| matches!(entry.kind(), EntryType::Highlight))
+        .map(|highlight| Annotation {
+            highlight,
+            notes: vec![],
+        })
+        .collect();
+    for note in notes {
+        let at = note.position().start();
+        let target = annotations
+            .iter_mut()
+            .filter(|annotation| same_book(&annotation.highlight, &note))
+            .map(|annotation| {
+                let location = annotation.highlight.position();
+                let score = (location.distance(at), location.end().abs_diff(at));
+                (score, annotation)
+            })
+            .filter(|((distance, _), _)| *distance <= window)
+            .min_by_key(|(score, _)| *score);
+        if let Some((_, annotation)) = target {
+            annotation.notes.push(note);
+        }
+    }
+    annotations
+}
+
+/// Notes whose location falls inside no highlight of the same book, usually
+/// because the highlight was deleted and the note kept. Input order is kept.
+pub fn orphan_notes(entries: &[Entry]) -> Vec<&Entry> {
+    entries
+        .iter()
+        .filter(|note| matches!(note.kind(), EntryType::Note))
+        .filter(|note| {
+            let at = note.position().start();
+            !entries.iter().any(|highlight| {
+                matches!(highlight.kind(), EntryType::Highlight)
+                    && same_book(highlight, note)
+                    && highlight.position().contains(at)
+            })
+        })
+        .collect()
+}
+
+/// Highlights of the same book that start at the same location but read
+/// differently, usually because the reader adjusted the selection, one
+/// cluster per location. A text that repeats within a cluster is only kept
+/// the first time; clusters left with a single highlight are not returned.
+/// Clusters are ordered by book and location, their highlights by input
+/// order.
+pub fn revised_highlights(entries: &[Entry]) -> Vec<Vec<&Entry>> {
+    let mut clusters: BTreeMap<(&str, &str, u64), Vec<&Entry>> = BTreeMap::new();
+    for entry in entries {
+        if *entry.kind() != EntryType::Highlight {
+            continue;
+        }
+        let cluster = clusters
+            .entry((entry.title(), entry.author(), entry.position().start()))
+            .or_default();
+        if cluster.iter().all(|seen| seen.text() != entry.text()) {
+            cluster.push(entry);
+        }
+    }
+    clusters
+        .into_values()
+        .filter(|cluster| cluster.len() > 1)
+        .collect()
+}
+
+/// Locations between two highlights that [`stitch_adjacent`] still treats as
+/// one passage when no gap is given: only highlights that directly follow
+/// each other.
+pub const DEFAULT_STITCH_GAP: u64 = 1;
+
+/// Joins highlights of the same book that follow each other, with at most
+/// `max_gap` locations from the end of one to the start of the next, into a
+/// single passage whose text is theirs joined with a space. Highlights that
+/// overlap the passage so far, usually edits of one another, are left alone,
+/// as are entries without a location; one that ends past the passage starts
+/// the next passage in its place. The passage takes the place, page and date
+/// of its first highlight; every other entry keeps its position in the input.
+pub fn stitch_adjacent(entries: Vec<Entry>, max_gap: u64) -> Vec<Entry> {
+    let mut by_book: BTreeMap<(&str, &str), Vec<usize>> = BTreeMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        if *entry.kind() == EntryType::Highlight && entry.location().is_some() {
+            by_book
+                .entry((entry.title(), entry.author()))
+                .or_default()
+                .push(index);
+        }
+    }
+    // Index of the first highlight of each passage, mapped to the rest.
+    let mut passages: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    let mut absorbed = vec![false; entries.len()];
+    for mut indices in by_book.into_values() {
+        indices.sort_by_cached_key(|&index| entries[index].sort_key());
+        let mut first = indices[0];
+        let mut end = entries[first].position().end();
+        for &index in &indices[1..] {
+            let location = entries[index].position();
+            if location.start() > end && location.start() - end <= max_gap {
+                passages.entry(first).or_default().push(index);
+                absorbed[index] = true;
+                end = location.end();
+            } else if location.end() > end {
+                first = index;
+                end = location.end();
+            }
+        }
+    }
+
+    let mut slots: Vec<Option<Entry>> = entries.into_iter().map(Some).collect();
+    let mut stitched = Vec::with_capacity(slots.len());
+    for index in 0..slots.len() {
+        if absorbed[index] {
+            continue;
+        }
+        let mut entry = slots[index].take().expect("each entry is taken once");
+        if let Some(rest) = passages.get(&index) {
+            for &next in rest {
+                let next = slots[next].take().expect("each entry is taken once");
+                entry.text = format!("{} {}", entry.text, next.text);
+                let start = entry.position().start();
+                entry.location = Some(
+                    Location::try_from((start, next.position().end()))
+                        .expect("passages are sorted by location"),
+                );
+            }
+        }
+        stitched.push(entry);
+    }
+    stitched
+}
+
+/// Drops notes whose text only repeats the highlight they annotate, comparing
+/// case-insensitively with whitespace collapsed.
+pub fn drop_redundant_notes(annotations: Vec<Annotation>) -> Vec<Annotation> {
+    annotations
+        .into_iter()
+        .map(|mut annotation| {
+            let highlight = normalize_text(annotation.highlight.text());
+            annotation
+                .notes
+                .retain(|note| normalize_text(note.text()) != highlight);
+            annotation
+        })
+        .collect()
+}
+
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn same_book(a: &Entry, b: &Entry) -> bool {
+    a.title() == b.title() && a.author() == b.author()
+}
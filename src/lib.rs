@@ -0,0 +1,31 @@
+pub mod discover;
+pub mod exporter;
+pub mod file_parser;
+pub mod filters;
+pub mod grouping;
+pub mod options;
+pub mod render;
+pub mod state;
+pub mod stats;
+
+#[cfg(feature = "polars")]
+pub mod dataframe;
+#[cfg(feature = "encoding")]
+pub mod encoding;
+#[cfg(feature = "serde")]
+pub mod export;
+#[cfg(feature = "serde")]
+pub mod schema;
+#[cfg(feature = "sidecar")]
+pub mod sidecar;
+
+pub use crate::file_parser::{
+    count_blocks, count_entries, parse_block, parse_bytes, parse_bytes_lossy, parse_file,
+    parse_file_lenient, parse_reader, parse_reader_iter, parse_reader_lenient, parse_str,
+    DatePrecision, Entry, EntryIter, EntryType, Location, MetaRegex, Page, ParseError, ParseReport,
+    ParseWarning,
+};
+pub use crate::options::ParseOptions;
+
+#[cfg(feature = "tokio")]
+pub use crate::file_parser::parse_file_async;
@@ -1,14 +1,621 @@
 mod arg_parser;
-mod file_parser;
 
-use crate::arg_parser::Args;
-use crate::file_parser::parse_file;
-use clap::Parser;
+use crate::arg_parser::{Args, Command};
+use clap::{Parser, ValueEnum};
+#[cfg(feature = "serde")]
+use clippings_parser::export;
+use clippings_parser::exporter::{
+    ClippingsExporter, DebugExporter, LineExporter, Registry, TextExporter,
+};
+#[cfg(feature = "serde")]
+use clippings_parser::exporter::{CsvExporter, JsonExporter, NestedJsonExporter, TsvExporter};
+use clippings_parser::file_parser::{self, open_file};
+use clippings_parser::grouping;
+use clippings_parser::{
+    discover, filters, render, state, stats, Entry, ParseError, ParseOptions, ParseReport,
+};
+use indicatif::ProgressBar;
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
+
+fn open_input(path: &Path, progress: bool) -> Result<(Box<dyn BufRead>, ProgressBar), ParseError> {
+    let show_progress = progress && io::stderr().is_terminal();
+    if path == Path::new("-") {
+        let bar = if show_progress {
+            ProgressBar::new_spinner()
+        } else {
+            ProgressBar::hidden()
+        };
+        let reader = BufReader::new(bar.wrap_read(io::stdin()));
+        Ok((Box::new(reader), bar))
+    } else {
+        let file = open_file(path)?;
+        let bar = if show_progress {
+            ProgressBar::new(file.metadata()?.len())
+        } else {
+            ProgressBar::hidden()
+        };
+        let reader = BufReader::new(bar.wrap_read(file));
+        Ok((Box::new(reader), bar))
+    }
+}
+
+/// Expands directories into the clippings files found beneath them.
+fn input_files(args: &Args) -> Vec<PathBuf> {
+    if args.clippings.is_empty() {
+        return vec![PathBuf::from("-")];
+    }
+    let mut files = vec![];
+    for path in &args.clippings {
+        if path.is_dir() {
+            let found = discover::find_clippings_files(path, args.all_txt)
+                .unwrap_or_else(|err| fail(path, err));
+            if !args.quiet {
+                eprintln!(
+                    "found {} clippings files in {}",
+                    found.len(),
+                    path.display()
+                );
+            }
+            files.extend(found);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+/// Parser settings chosen on the command line, except --lenient which
+/// [`validate`] always turns on.
+fn parse_options(args: &Args) -> ParseOptions {
+    let options = ParseOptions::new()
+        .lossy(args.lossy)
+        .no_author(args.no_author);
+    let options = match &args.meta_regex {
+        Some(meta_regex) => options.meta_regex(meta_regex.clone()),
+        None => options,
+    };
+    #[cfg(feature = "encoding")]
+    let options = match args.encoding {
+        Some(encoding) => options.encoding(encoding),
+        None => options,
+    };
+    options
+}
+
+/// Parses one input, printing the entries skipped by --lenient and any
+/// warnings. Returns the entries with the number that were skipped.
+fn read_file(args: &Args, path: &Path) -> Result<(Vec<Entry>, usize), ParseError> {
+    let (reader, bar) = open_input(path, args.progress)?;
+    let report = parse_options(args)
+        .lenient(args.lenient)
+        .parse_reader(reader);
+    bar.finish_and_clear();
+    let report = report?;
+    for (line, err, _) in &report.errors {
+        eprintln!("{}: line {}: {}", path.display(), line, err);
+    }
+    for (line, warning) in &report.warnings {
+        eprintln!("{}: line {}: warning: {}", path.display(), line, warning);
+    }
+    if args.verbose {
+        eprintln!("{}: {}", path.display(), variants(&report));
+    }
+    Ok((report.entries, report.errors.len()))
+}
+
+/// `format variants: location-colon 2, page-only 1`, in name order.
+fn variants(report: &ParseReport) -> String {
+    let Some(counts) = report.format_variants() else {
+        return "format variants: unavailable with --meta-regex".to_string();
+    };
+    if counts.is_empty() {
+        return "format variants: none".to_string();
+    }
+    let counts = counts
+        .into_iter()
+        .sorted()
+        .map(|(variant, count)| format!("{} {}", variant, thousands(count)))
+        .join(", ");
+    format!("format variants: {}", counts)
+}
+
+/// Reports every entry that fails to parse, with its line number, and
+/// returns whether all inputs were valid. Nothing is printed on success.
+fn validate(args: &Args) -> bool {
+    let options = parse_options(args).lenient(true);
+    let mut valid = true;
+    for path in input_files(args) {
+        let report = open_input(&path, args.progress).and_then(|(reader, bar)| {
+            let report = options.parse_reader(reader);
+            bar.finish_and_clear();
+            report
+        });
+        let report = match report {
+            Ok(report) => report,
+            Err(err) => {
+                eprintln!("{}: {}", path.display(), err);
+                valid = false;
+                continue;
+            }
+        };
+        for (line, err, _) in &report.errors {
+            eprintln!("{}: line {}: {}", path.display(), line, err);
+            valid = false;
+        }
+    }
+    valid
+}
+
+/// Entries of every input and the number skipped by --lenient. An input that
+/// cannot be read or parsed ends the program with [`EXIT_FAILURE`].
+fn read_entries(args: &Args) -> (Vec<Entry>, usize) {
+    let files = input_files(args);
+    let start = Instant::now();
+    let mut entries = vec![];
+    let mut failed = 0;
+    for path in &files {
+        match read_file(args, path) {
+            Ok((parsed, skipped)) => {
+                entries.extend(parsed);
+                failed += skipped;
+            }
+            Err(err) => {
+                eprintln!("{}: {}", path.display(), err);
+                process::exit(EXIT_FAILURE);
+            }
+        }
+    }
+    if args.timing {
+        eprintln!("{}", timing(entries.len(), start.elapsed()));
+    }
+    if !args.quiet {
+        eprintln!("{}", summary(&entries, files.len()));
+    }
+    (entries, failed)
+}
+
+/// Reports `err` against `path` and ends the program with [`EXIT_FAILURE`].
+fn fail(path: &Path, err: impl std::fmt::Display) -> ! {
+    eprintln!("{}: {}", path.display(), err);
+    process::exit(EXIT_FAILURE);
+}
+
+/// Exit status when an input cannot be read or nothing in it parses, or when
+/// the output cannot be written.
+const EXIT_FAILURE: i32 = 1;
+/// Exit status when --lenient skipped some entries but others parsed.
+const EXIT_PARTIAL: i32 = 2;
+
+fn exit_code(parsed: usize, failed: usize) -> i32 {
+    match (parsed, failed) {
+        (_, 0) => 0,
+        (0, _) => EXIT_FAILURE,
+        _ => EXIT_PARTIAL,
+    }
+}
+
+/// `parsed 4,812 entries from 1 file (3 bookmarks, 4,790 highlights, 19 notes)`
+fn summary(entries: &[Entry], files: usize) -> String {
+    format!(
+        "parsed {} entries from {} file{} ({})",
+        thousands(entries.len()),
+        thousands(files),
+        if files == 1 { "" } else { "s" },
+        breakdown(stats::type_counts(entries))
+    )
+}
+
+/// `3 bookmarks, 4,790 highlights, 19 notes`, with articles only when there
+/// are any since few devices produce them.
+fn breakdown(counts: stats::TypeCounts) -> String {
+    let mut parts = vec![
+        format!("{} bookmarks", thousands(counts.bookmarks)),
+        format!("{} highlights", thousands(counts.highlights)),
+        format!("{} notes", thousands(counts.notes)),
+    ];
+    if counts.articles > 0 {
+        parts.push(format!("{} articles", thousands(counts.articles)));
+    }
+    parts.join(", ")
+}
+
+/// `parsed 4,812 entries in 35 ms (137,485 entries/s)`
+fn timing(entries: usize, elapsed: Duration) -> String {
+    let rate = entries as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+    format!(
+        "parsed {} entries in {} ms ({} entries/s)",
+        thousands(entries),
+        elapsed.as_millis(),
+        thousands(rate as usize)
+    )
+}
+
+fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// The filters that only pick entries by what they say: book, author, kind,
+/// location, date and length, after --strip-bidi. Collapsing, sampling and
+/// redacting come after them in [`apply_filters`]; `diff` only selects.
+fn select(args: &Args, mut entries: Vec<Entry>) -> Vec<Entry> {
+    if args.strip_bidi {
+        entries = filters::strip_bidi_controls(entries);
+    }
+    if let Some(author) = &args.author {
+        entries = filters::filter_by_author(entries, author);
+    }
+    if let Some(title) = &args.book {
+        entries = filters::filter_by_book(entries, title);
+    } else if let Some(title) = &args.book_contains {
+        entries = filters::filter_by_book_contains(entries, title);
+    }
+    if (args.book.is_some() || args.book_contains.is_some()) && !args.quiet {
+        let books = stats::stats(&entries).books;
+        if books > 1 {
+            eprintln!("note: {} books match, including all of them", books);
+        }
+    }
+    if let Some(range) = &args.location {
+        entries = filters::filter_by_location(&entries, range)
+            .into_iter()
+            .cloned()
+            .collect();
+    }
+    if let Some(kind) = args.kind {
+        entries = filters::filter_by_kind(entries, kind.into());
+    }
+    if args.since.is_some() || args.until.is_some() {
+        entries = filters::filter_by_date(entries, args.since, args.until);
+    }
+    if let Some(min) = args.min_length {
+        entries = if args.min_length_all_kinds {
+            filters::filter_min_length_all(entries, min)
+        } else {
+            filters::filter_min_length(entries, min)
+        };
+    }
+    entries
+}
+
+fn apply_filters(args: &Args, entries: Vec<Entry>) -> Vec<Entry> {
+    let mut entries = select(args, entries);
+    if args.keep_latest {
+        entries = filters::keep_latest(entries);
+    }
+    if args.one_per_location {
+        entries = filters::collapse_by_location(entries);
+    }
+    if let Some(gap) = args.stitch {
+        entries = grouping::stitch_adjacent(entries, gap);
+    }
+    if let Some(path) = &args.newer_than_file {
+        let since = state::read_state(path).unwrap_or_else(|err| fail(path, err));
+        entries = state::newer_than(entries, since);
+    }
+    if let Some(min) = args.min_highlights {
+        let kept: HashSet<(String, String)> =
+            grouping::filter_books_min(grouping::group_by_book(entries.clone()), min)
+                .into_iter()
+                .map(|book| (book.title, book.author))
+                .collect();
+        entries.retain(|entry| {
+            kept.contains(&(entry.title().to_string(), entry.author().to_string()))
+        });
+    }
+    if let Some(n) = args.random {
+        entries = filters::sample_highlights(entries, n, args.seed);
+    }
+    if args.redact {
+        entries = filters::redact_text(entries);
+    }
+    entries
+}
+
+/// The built-in formats, configured from the command line and registered
+/// under their `--format` names.
+fn exporters(args: &Args) -> Registry {
+    let mut registry = Registry::new();
+    registry
+        .register(
+            "debug",
+            DebugExporter {
+                include_bookmarks: args.include_bookmarks,
+                date_format: args.date_format.clone(),
+                escape_control: io::stdout().is_terminal(),
+            },
+        )
+        .register(
+            "text",
+            TextExporter {
+                include_bookmarks: args.include_bookmarks,
+            },
+        )
+        .register("line", LineExporter { width: args.width })
+        .register("clippings", ClippingsExporter);
+    #[cfg(feature = "serde")]
+    registry
+        .register(
+            "json",
+            JsonExporter {
+                envelope: args.envelope,
+            },
+        )
+        .register(
+            "nested-json",
+            NestedJsonExporter {
+                order: args.group_sort.into(),
+                drop_redundant_notes: args.drop_redundant_notes,
+            },
+        )
+        .register(
+            "csv",
+            CsvExporter {
+                columns: columns(args).to_vec(),
+            },
+        )
+        .register(
+            "tsv",
+            TsvExporter {
+                columns: columns(args).to_vec(),
+            },
+        );
+    registry
+}
+
+fn write_output(args: &Args, entries: Vec<Entry>) {
+    let format = args
+        .format
+        .to_possible_value()
+        .expect("formats are never hidden");
+    let registry = exporters(args);
+    let exporter = registry
+        .get(format.get_name())
+        .expect("every format has an exporter");
+    if let Err(err) = exporter.export(&entries, &mut io::stdout().lock()) {
+        match err {
+            ParseError::FileReadError(err) => output_failed(err),
+            err => {
+                eprintln!("{}", err);
+                process::exit(EXIT_FAILURE);
+            }
+        }
+    }
+}
+
+/// Runs `write` on a locked stdout, ending the program through
+/// [`output_failed`] when writing fails, such as on a closed pipe.
+fn to_stdout(write: impl FnOnce(&mut io::StdoutLock) -> io::Result<()>) {
+    if let Err(err) = write(&mut io::stdout().lock()) {
+        output_failed(err);
+    }
+}
+
+fn output_failed(err: io::Error) -> ! {
+    eprintln!("cannot write the output: {}", err);
+    process::exit(EXIT_FAILURE);
+}
+
+#[cfg(feature = "serde")]
+fn columns(args: &Args) -> &[export::Column] {
+    if args.columns.is_empty() {
+        &export::Column::DEFAULT
+    } else {
+        &args.columns
+    }
+}
+
+fn print_books(out: &mut impl Write, entries: Vec<Entry>, latest: bool) -> io::Result<()> {
+    for book in grouping::group_by_book(entries) {
+        let spots = stats::distinct_locations(&book);
+        writeln!(
+            out,
+            "{} ({}): {} entries ({}), {} distinct spot{}",
+            book.title,
+            book.author,
+            book.entries.len(),
+            breakdown(stats::type_counts(&book.entries)),
+            spots,
+            if spots == 1 { "" } else { "s" }
+        )?;
+        if latest {
+            if let Some(entry) = stats::latest_per_book(&book.entries).first() {
+                writeln!(out, "  latest: {}", entry)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_stats(out: &mut impl Write, entries: &[Entry]) -> io::Result<()> {
+    let totals = stats::stats(entries);
+    writeln!(out, "entries: {}", thousands(totals.entries))?;
+    writeln!(out, "books: {}", thousands(totals.books))?;
+    writeln!(out, "highlights: {}", thousands(totals.highlights))?;
+    writeln!(out, "notes: {}", thousands(totals.notes))?;
+    writeln!(out, "bookmarks: {}", thousands(totals.bookmarks))?;
+    if totals.articles > 0 {
+        writeln!(out, "articles: {}", thousands(totals.articles))?;
+    }
+    writeln!(
+        out,
+        "orphan notes: {}",
+        thousands(grouping::orphan_notes(entries).len())
+    )?;
+    writeln!(
+        out,
+        "revised highlights: {}",
+        thousands(
+            grouping::revised_highlights(entries)
+                .iter()
+                .map(Vec::len)
+                .sum()
+        )
+    )?;
+    if let Some((first, last)) = stats::reading_span(entries) {
+        writeln!(out, "reading span: {} to {}", first, last)?;
+    }
+    let streak = stats::longest_streak(entries);
+    writeln!(
+        out,
+        "longest streak: {} day{}",
+        streak,
+        if streak == 1 { "" } else { "s" }
+    )?;
+    let busiest = stats::daily_counts(entries)
+        .into_iter()
+        .max_by_key(|(day, count)| (*count, std::cmp::Reverse(*day)));
+    if let Some((day, count)) = busiest {
+        writeln!(out, "busiest day: {} ({} entries)", day, thousands(count))?;
+    }
+    let books = grouping::group_by_book(entries.to_vec());
+    let ranked = stats::rank_by_density(&books);
+    if !ranked.is_empty() {
+        writeln!(out, "highlights per 1000 locations:")?;
+    }
+    for (book, density) in ranked {
+        writeln!(out, "  {:>8.1}  {} ({})", density, book.title, book.author)?;
+    }
+    Ok(())
+}
+
+/// Blocks in every input, for `--count --fast`.
+fn count_blocks(args: &Args) -> usize {
+    input_files(args)
+        .iter()
+        .map(|path| {
+            let (reader, bar) =
+                open_input(path, args.progress).unwrap_or_else(|err| fail(path, err));
+            let count = file_parser::count_blocks(reader);
+            bar.finish_and_clear();
+            count.unwrap_or_else(|err| fail(path, err))
+        })
+        .sum()
+}
+
+/// Why --preserve-order cannot be honoured, if it cannot.
+fn order_conflict(args: &Args) -> Option<&'static str> {
+    if !args.preserve_order {
+        return None;
+    }
+    match args.command {
+        Some(Command::Merge { .. }) => {
+            return Some("--preserve-order cannot be used with merge, which sorts")
+        }
+        Some(Command::Books { .. }) => {
+            return Some("--preserve-order cannot be used with books, which groups by book")
+        }
+        Some(Command::Stats) => {
+            return Some("--preserve-order cannot be used with stats, which groups by book")
+        }
+        _ => {}
+    }
+    #[cfg(feature = "serde")]
+    if args.format == arg_parser::Format::NestedJson {
+        return Some("--preserve-order cannot be used with the nested-json format");
+    }
+    None
+}
+
+fn merge(args: &Args, entries: Vec<Entry>, output: &Path, normalized: bool) {
+    let mut entries = if normalized {
+        filters::dedup_normalized(entries)
+    } else {
+        filters::dedup(entries)
+    };
+    grouping::sort_by_book_and_location(&mut entries);
+    let text = render::to_clippings(&entries).unwrap_or_else(|err| fail(output, err));
+    if let Err(err) = fs::write(output, text) {
+        fail(output, err);
+    }
+    if !args.quiet {
+        eprintln!(
+            "wrote {} entries to {}",
+            thousands(entries.len()),
+            output.display()
+        );
+    }
+}
+
+/// Prints the `added` and `removed` sections of [`filters::diff`], one
+/// [`render::to_line`] per entry, and returns the exit status for the
+/// entries --lenient skipped in either file.
+fn print_diff(args: &Args, old: &Path, new: &Path) -> i32 {
+    let read = |path: &Path| read_file(args, path).unwrap_or_else(|err| fail(path, err));
+    let ((old, old_failed), (new, new_failed)) = (read(old), read(new));
+    let code = exit_code(old.len() + new.len(), old_failed + new_failed);
+    let (old, new) = (select(args, old), select(args, new));
+    let diff = filters::diff(&old, &new);
+    to_stdout(|out| {
+        for (label, entries) in [("added", diff.added), ("removed", diff.removed)] {
+            writeln!(out, "{}: {}", label, thousands(entries.len()))?;
+            for entry in entries {
+                writeln!(out, "  {}", render::to_line(entry, args.width))?;
+            }
+        }
+        Ok(())
+    });
+    code
+}
 
 fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("off")).init();
     let args = Args::parse();
-    let result = parse_file(args.clippings).unwrap();
-    for item in result {
-        println!("{}", item);
+    if let Some(conflict) = order_conflict(&args) {
+        use clap::CommandFactory;
+        Args::command()
+            .error(clap::error::ErrorKind::ArgumentConflict, conflict)
+            .exit();
+    }
+    if let Some(Command::Validate) = args.command {
+        process::exit(if validate(&args) { 0 } else { EXIT_FAILURE });
+    }
+    if let Some(Command::Diff { old, new }) = &args.command {
+        process::exit(print_diff(&args, old, new));
+    }
+    if args.fast {
+        let count = count_blocks(&args);
+        to_stdout(|out| writeln!(out, "{}", count));
+        return;
+    }
+    let (entries, failed) = read_entries(&args);
+    let code = exit_code(entries.len(), failed);
+    let entries = apply_filters(&args, entries);
+    let newest = entries.iter().map(Entry::creation_date).max();
+    if let Some(Command::Books { latest }) = args.command {
+        to_stdout(|out| print_books(out, entries, latest));
+    } else if let Some(Command::Stats) = args.command {
+        to_stdout(|out| print_stats(out, &entries));
+    } else if let Some(Command::Merge {
+        output,
+        dedup_normalized,
+    }) = &args.command
+    {
+        merge(&args, entries, output, *dedup_normalized);
+    } else if args.count {
+        to_stdout(|out| writeln!(out, "{}", entries.len()));
+    } else {
+        write_output(&args, entries);
+    }
+    if let (Some(path), Some(newest)) = (&args.newer_than_file, newest) {
+        if let Err(err) = state::write_state(path, newest) {
+            fail(path, err);
+        }
+    }
+    if code != 0 {
+        process::exit(code);
     }
 }
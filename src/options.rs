@@ -0,0 +1,144 @@
+#[cfg(feature = "encoding")]
+use crate::encoding::{self, InputEncoding};
+use crate::file_parser::{
+    open_file, parse_buffered, parse_buffered_lenient, Entry, Grammar, MetaRegex, ParseError,
+    ParseReport,
+};
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Parser settings, built with chained setters and starting from the
+/// defaults the free functions use:
+///
+/// ```no_run
+/// use clippings_parser::ParseOptions;
+///
+/// let report = ParseOptions::new()
+///     .lenient(true)
+///     .parse_file("My Clippings.txt")?;
+/// for (line, err, _) in &report.errors {
+///     eprintln!("line {}: {}", line, err);
+/// }
+/// # Ok::<(), clippings_parser::ParseError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    lenient: bool,
+    lossy: bool,
+    meta_regex: Option<MetaRegex>,
+    no_author: bool,
+    #[cfg(feature = "encoding")]
+    encoding: Option<InputEncoding>,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collect entries that fail to parse in [`ParseReport::errors`] instead
+    /// of failing on the first one.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Replace invalid UTF-8 with U+FFFD instead of failing.
+    pub fn lossy(mut self, lossy: bool) -> Self {
+        self.lossy = lossy;
+        self
+    }
+
+    /// Read the second line of each entry with `meta_regex` instead of the
+    /// built-in grammar.
+    pub fn meta_regex(mut self, meta_regex: MetaRegex) -> Self {
+        self.meta_regex = Some(meta_regex);
+        self
+    }
+
+    /// Take the whole first line of each entry as the title and leave the
+    /// author empty. By default a line ending in a parenthesized part is
+    /// split there, so `Brackets (a memoir)` is read as the book `Brackets`
+    /// by `a memoir`: the format cannot tell a title that ends in parentheses
+    /// from one followed by its author. A line without parentheses, which is
+    /// an error by default, becomes a title.
+    pub fn no_author(mut self, no_author: bool) -> Self {
+        self.no_author = no_author;
+        self
+    }
+
+    /// Decode the input with `encoding` instead of expecting UTF-8. Takes
+    /// precedence over [`ParseOptions::lossy`].
+    #[cfg(feature = "encoding")]
+    pub fn encoding(mut self, encoding: InputEncoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Parses a file. Entries carry their source offset unless the input had
+    /// to be decoded or repaired first, which changes its byte layout.
+    pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<ParseReport, ParseError> {
+        let file = open_file(path.as_ref())?;
+        self.parse(BufReader::new(file), true)
+    }
+
+    /// Parses any reader. In strict mode the report's `errors` are always
+    /// empty, since the first failure is returned as `Err`.
+    pub fn parse_reader<R: BufRead>(&self, reader: R) -> Result<ParseReport, ParseError> {
+        self.parse(reader, false)
+    }
+
+    fn parse<R: BufRead>(
+        &self,
+        mut reader: R,
+        track_offsets: bool,
+    ) -> Result<ParseReport, ParseError> {
+        match self.decode(&mut reader)? {
+            Some(text) => self.parse_buffered(text.as_bytes(), false),
+            None => self.parse_buffered(reader, track_offsets),
+        }
+    }
+
+    /// The whole input as UTF-8 when it needs decoding, `None` when it can be
+    /// streamed as is.
+    fn decode<R: BufRead>(&self, reader: &mut R) -> Result<Option<String>, ParseError> {
+        #[cfg(feature = "encoding")]
+        if let Some(encoding) = self.encoding {
+            let bytes = read_all(reader)?;
+            return Ok(Some(encoding::decode(&bytes, encoding).into_owned()));
+        }
+        if self.lossy {
+            let bytes = read_all(reader)?;
+            return Ok(Some(String::from_utf8_lossy(&bytes).into_owned()));
+        }
+        Ok(None)
+    }
+
+    fn parse_buffered<R: BufRead>(
+        &self,
+        reader: R,
+        track_offsets: bool,
+    ) -> Result<ParseReport, ParseError> {
+        let grammar = Grammar {
+            meta: self.meta_regex.as_ref().unwrap_or(MetaRegex::builtin()),
+            no_author: self.no_author,
+        };
+        let mut report = if self.lenient {
+            parse_buffered_lenient(reader, track_offsets, grammar)
+        } else {
+            let entries: Vec<Entry> = parse_buffered(reader, track_offsets, grammar)?;
+            ParseReport {
+                entries,
+                ..ParseReport::default()
+            }
+        };
+        report.custom_meta = self.meta_regex.is_some();
+        Ok(report)
+    }
+}
+
+fn read_all<R: Read>(reader: &mut R) -> Result<Vec<u8>, ParseError> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
@@ -0,0 +1,148 @@
+use crate::file_parser::{Entry, EntryType, ParseError, KINDLE_DATE_FORMAT, SEPARATOR};
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// Replaces control characters other than newline with visible escapes such
+/// as `\t` or `\x0b`, so stray bytes in a clipping cannot garble a terminal.
+pub fn escape_control(s: &str) -> Cow<'_, str> {
+    if !s.chars().any(needs_escape) {
+        return Cow::Borrowed(s);
+    }
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if needs_escape(c) && (c as u32) < 0x100 => {
+                write!(escaped, "\\x{:02x}", c as u32).unwrap()
+            }
+            c if needs_escape(c) => write!(escaped, "\\u{{{:04x}}}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+fn needs_escape(c: char) -> bool {
+    c.is_control() && c != '\n'
+}
+
+/// Bare highlight texts separated by blank lines, without any metadata.
+/// Notes and bookmarks are skipped.
+pub fn to_text(entries: &[Entry]) -> String {
+    to_text_with(entries, false)
+}
+
+/// Like [`to_text`], but with `include_bookmarks` each bookmark is kept as a
+/// [`bookmark_marker`] line.
+pub fn to_text_with(entries: &[Entry], include_bookmarks: bool) -> String {
+    entries
+        .iter()
+        .filter_map(|entry| match entry.kind() {
+            EntryType::Highlight => Some(format!("{}\n", entry.text())),
+            EntryType::Bookmark if include_bookmarks => {
+                Some(format!("{}\n", bookmark_marker(entry)))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `[Bookmark at location 150]`, standing in for a bookmark's empty text in
+/// human-readable output.
+pub fn bookmark_marker(entry: &Entry) -> String {
+    match (entry.location(), entry.page()) {
+        (None, Some(page)) => format!("[Bookmark at page {}]", page.number()),
+        _ => format!("[Bookmark at location {}]", entry.position().start()),
+    }
+}
+
+/// One dense line per entry for eyeballing and grepping:
+/// `[H] Title — Author @100-105 (2022-01-03): Sing to me…`. The text is kept
+/// on the line and cut to `width` characters with an ellipsis; bookmarks
+/// have no text.
+pub fn to_line(entry: &Entry, width: usize) -> String {
+    let marker = match entry.kind() {
+        EntryType::Highlight => 'H',
+        EntryType::Note => 'N',
+        EntryType::Bookmark => 'B',
+        EntryType::Article => 'A',
+    };
+    let mut line = format!(
+        "[{}] {} \u{2014} {} @{} ({})",
+        marker,
+        entry.title(),
+        entry.author(),
+        location_range(entry),
+        entry.creation_date().date()
+    );
+    if *entry.kind() != EntryType::Bookmark {
+        let text = entry
+            .text()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        line.push_str(": ");
+        if text.chars().count() > width {
+            let cut: String = text.chars().take(width).collect();
+            line.push_str(cut.trim_end());
+            line.push('\u{2026}');
+        } else {
+            line.push_str(&text);
+        }
+    }
+    line
+}
+
+/// `100-105`, or just `100` for a single location. Entries without a
+/// location use their page.
+fn location_range(entry: &Entry) -> String {
+    let location = entry.position();
+    if location.start() == location.end() {
+        location.start().to_string()
+    } else {
+        format!("{}-{}", location.start(), location.end())
+    }
+}
+
+/// Writes entries back in the `My Clippings.txt` format, with `\n` line
+/// endings and English labels, so that parsing the result yields the same
+/// entries. The format has no escaping, so an entry whose text contains a
+/// `==========` line is refused with [`ParseError::SeparatorInText`] rather
+/// than written in a way that would split it in two.
+pub fn to_clippings(entries: &[Entry]) -> Result<String, ParseError> {
+    let mut out = String::new();
+    for entry in entries {
+        if entry.text().lines().any(|line| line == SEPARATOR) {
+            return Err(ParseError::SeparatorInText(entry.title().to_string()));
+        }
+        write_block(&mut out, entry);
+        writeln!(out, "{}", SEPARATOR).unwrap();
+    }
+    Ok(out)
+}
+
+/// One entry of [`to_clippings`] without the separator, see
+/// [`Entry::to_block_string`].
+pub(crate) fn write_block(out: &mut String, entry: &Entry) {
+    writeln!(out, "{} ({})", entry.title(), entry.author()).unwrap();
+    write!(out, "- Your {:?}", entry.kind()).unwrap();
+    if let Some(color) = entry.color() {
+        write!(out, " ({})", color).unwrap();
+    }
+    out.push_str(" on");
+    if let Some(page) = entry.page() {
+        write!(out, " page {} |", page.number()).unwrap();
+    }
+    if entry.location().is_some() {
+        write!(out, " Location {} |", location_range(entry)).unwrap();
+    }
+    writeln!(
+        out,
+        " Added on {}",
+        entry.creation_date().format(KINDLE_DATE_FORMAT)
+    )
+    .unwrap();
+    writeln!(out, "\n{}", entry.text()).unwrap();
+}
@@ -0,0 +1,60 @@
+//! The JSON shape of `--format json --envelope`, as types integrators can
+//! deserialize into instead of reverse-engineering the output.
+
+use crate::file_parser::{Entry, EntryType};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Version of the [`Envelope`] shape, increased on incompatible changes.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// `{ "version": 1, "generated_at": "...", "entries": [...] }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u32,
+    pub generated_at: DateTime<Utc>,
+    pub entries: Vec<EntryRecord>,
+}
+
+impl Envelope {
+    /// Wraps `entries` at the current [`SCHEMA_VERSION`], stamped with now.
+    pub fn new(entries: &[Entry]) -> Self {
+        Envelope {
+            version: SCHEMA_VERSION,
+            generated_at: Utc::now(),
+            entries: entries.iter().map(EntryRecord::from).collect(),
+        }
+    }
+}
+
+/// One entry, with the same fields as the bare `--format json` array.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryRecord {
+    pub title: String,
+    pub author: String,
+    pub kind: EntryType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    pub page: Option<u64>,
+    /// `[start, end]`, `null` for page-only books.
+    pub location: Option<(u64, u64)>,
+    pub creation_date: NaiveDateTime,
+    pub text: String,
+}
+
+impl From<&Entry> for EntryRecord {
+    fn from(entry: &Entry) -> Self {
+        EntryRecord {
+            title: entry.title().to_string(),
+            author: entry.author().to_string(),
+            kind: *entry.kind(),
+            color: entry.color().map(str::to_string),
+            page: entry.page().map(|page| page.number()),
+            location: entry
+                .location()
+                .map(|location| (location.start(), location.end())),
+            creation_date: entry.creation_date(),
+            text: entry.text().to_string(),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! A first step towards reading the structured annotation data newer Kindles
+//! keep next to `My Clippings.txt`. Only a JSON rendering of it is read, one
+//! book per file:
+//!
+//! ```json
+//! {
+//!   "title": "The Odyssey",
+//!   "authors": "Homer",
+//!   "annotations": [
+//!     {
+//!       "type": "highlight",
+//!       "color": "yellow",
+//!       "page": 5,
+//!       "startPosition": 100,
+//!       "endPosition": 102,
+//!       "created": "2022-01-03T10:15:30",
+//!       "text": "Sing to me of the man, Muse."
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `type` is `highlight`, `note` or `bookmark`; `color`, `page`,
+//! `endPosition` and `text` may be left out.
+
+use crate::file_parser::{DatePrecision, Entry, EntryType, Location, Page, ParseError};
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Sidecar {
+    title: String,
+    authors: String,
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Annotation {
+    #[serde(rename = "type")]
+    kind: Kind,
+    color: Option<String>,
+    page: Option<u64>,
+    start_position: u64,
+    end_position: Option<u64>,
+    created: NaiveDateTime,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Highlight,
+    Note,
+    Bookmark,
+}
+
+/// Reads a sidecar into the same entries [`parse_str`](crate::parse_str)
+/// gives for `My Clippings.txt`, so both sources can be combined.
+pub fn from_kindle_sidecar(json: &str) -> Result<Vec<Entry>, ParseError> {
+    let sidecar: Sidecar = serde_json::from_str(json).map_err(ParseError::InvalidSidecar)?;
+    sidecar
+        .annotations
+        .into_iter()
+        .map(|annotation| {
+            let end = annotation.end_position.unwrap_or(annotation.start_position);
+            Ok(Entry {
+                title: sidecar.title.clone(),
+                author: sidecar.authors.clone(),
+                kind: match annotation.kind {
+                    Kind::Highlight => EntryType::Highlight,
+                    Kind::Note => EntryType::Note,
+                    Kind::Bookmark => EntryType::Bookmark,
+                },
+                color: annotation.color,
+                page: annotation.page.map(Page::from),
+                location: Some(Location::try_from((annotation.start_position, end))?),
+                creation_date: annotation.created,
+                date_precision: DatePrecision::DateTime,
+                text: annotation.text,
+                source_offset: None,
+                raw_meta: None,
+            })
+        })
+        .collect()
+}
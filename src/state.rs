@@ -0,0 +1,42 @@
+use crate::file_parser::{Entry, ParseError};
+use chrono::NaiveDateTime;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// How the timestamp is stored, the same ISO-8601 form as the JSON export.
+const STATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Reads the creation date of the newest entry a previous run emitted. A
+/// missing or empty state file means nothing was emitted yet.
+pub fn read_state(path: &Path) -> Result<Option<NaiveDateTime>, ParseError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let value = contents.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    NaiveDateTime::parse_from_str(value, STATE_FORMAT)
+        .map(Some)
+        .map_err(|_| ParseError::InvalidDate(value.to_string()))
+}
+
+/// Stores `newest` for the next run, replacing the previous timestamp.
+pub fn write_state(path: &Path, newest: NaiveDateTime) -> Result<(), ParseError> {
+    fs::write(path, format!("{}\n", newest.format(STATE_FORMAT)))?;
+    Ok(())
+}
+
+/// Keeps entries created strictly after `last`, or all of them without one.
+pub fn newer_than(entries: Vec<Entry>, last: Option<NaiveDateTime>) -> Vec<Entry> {
+    match last {
+        Some(last) => entries
+            .into_iter()
+            .filter(|entry| entry.creation_date() > last)
+            .collect(),
+        None => entries,
+    }
+}
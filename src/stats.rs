@@ -0,0 +1,189 @@
+use crate::file_parser::{Entry, EntryType};
+use crate::grouping::Book;
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Totals over a set of entries, shared by every feature that reports counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub entries: usize,
+    pub books: usize,
+    pub highlights: usize,
+    pub notes: usize,
+    pub bookmarks: usize,
+    pub articles: usize,
+}
+
+pub fn stats(entries: &[Entry]) -> Stats {
+    let counts = type_counts(entries);
+    Stats {
+        entries: entries.len(),
+        books: entries
+            .iter()
+            .map(|entry| (entry.title(), entry.author()))
+            .collect::<HashSet<_>>()
+            .len(),
+        highlights: counts.highlights,
+        notes: counts.notes,
+        bookmarks: counts.bookmarks,
+        articles: counts.articles,
+    }
+}
+
+/// Number of entries of each [`EntryType`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeCounts {
+    pub highlights: usize,
+    pub notes: usize,
+    pub bookmarks: usize,
+    pub articles: usize,
+}
+
+pub fn type_counts(entries: &[Entry]) -> TypeCounts {
+    let mut counts = TypeCounts::default();
+    for entry in entries {
+        match entry.kind() {
+            EntryType::Highlight => counts.highlights += 1,
+            EntryType::Note => counts.notes += 1,
+            EntryType::Bookmark => counts.bookmarks += 1,
+            EntryType::Article => counts.articles += 1,
+        }
+    }
+    counts
+}
+
+/// The dates of the earliest and latest highlight, `None` without highlights.
+pub fn reading_span(entries: &[Entry]) -> Option<(NaiveDate, NaiveDate)> {
+    let days = highlight_days(entries);
+    Some((*days.first()?, *days.last()?))
+}
+
+/// The longest run of consecutive calendar days with at least one highlight.
+pub fn longest_streak(entries: &[Entry]) -> u32 {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in highlight_days(entries) {
+        current = match previous {
+            Some(previous) if previous.succ_opt() == Some(day) => current + 1,
+            _ => 1,
+        };
+        longest = longest.max(current);
+        previous = Some(day);
+    }
+    longest
+}
+
+fn highlight_days(entries: &[Entry]) -> BTreeSet<NaiveDate> {
+    entries
+        .iter()
+        .filter(|entry| *entry.kind() == EntryType::Highlight)
+        .map(|entry| entry.creation_date().date())
+        .collect()
+}
+
+/// Number of entries of any kind created on each day, for activity charts.
+/// Days without entries are absent rather than zero.
+pub fn daily_counts(entries: &[Entry]) -> BTreeMap<NaiveDate, usize> {
+    let mut counts = BTreeMap::new();
+    for entry in entries {
+        *counts.entry(entry.creation_date().date()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Estimates how far through the book each entry is, as `(location start,
+/// percent)` pairs in entry order. The largest location end seen in the book
+/// stands in for its length, so even the last entry's start is usually
+/// below 100%.
+pub fn location_percentiles(book: &Book) -> Vec<(u64, f64)> {
+    let max = max_location(book);
+    book.entries
+        .iter()
+        .map(|entry| {
+            let start = entry.position().start();
+            (start, percent_through(start, max))
+        })
+        .collect()
+}
+
+/// Number of different locations the book's highlights start at. Edited or
+/// repeated highlights share a location, so this can be below the count of
+/// highlights.
+pub fn distinct_locations(book: &Book) -> usize {
+    book.entries
+        .iter()
+        .filter(|entry| *entry.kind() == EntryType::Highlight)
+        .map(|entry| entry.position().start())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+pub(crate) fn max_location(book: &Book) -> u64 {
+    book.entries
+        .iter()
+        .map(|entry| entry.position().end())
+        .max()
+        .unwrap_or(0)
+}
+
+/// `location` as a percentage of `max`, `0.0` when the book has no extent.
+pub(crate) fn percent_through(location: u64, max: u64) -> f64 {
+    if max == 0 {
+        0.0
+    } else {
+        (location.min(max) as f64 / max as f64) * 100.0
+    }
+}
+
+/// Highlights per 1000 locations between the book's first and last entry,
+/// `0.0` when all its entries sit at one location.
+pub fn highlight_density(book: &Book) -> f64 {
+    let min = book
+        .entries
+        .iter()
+        .map(|entry| entry.position().start())
+        .min()
+        .unwrap_or(0);
+    let span = max_location(book).saturating_sub(min);
+    if span == 0 {
+        return 0.0;
+    }
+    let highlights = book
+        .entries
+        .iter()
+        .filter(|entry| *entry.kind() == EntryType::Highlight)
+        .count();
+    highlights as f64 / span as f64 * 1000.0
+}
+
+/// Books paired with their [`highlight_density`], densest first.
+pub fn rank_by_density(books: &[Book]) -> Vec<(&Book, f64)> {
+    let mut ranked: Vec<(&Book, f64)> = books
+        .iter()
+        .map(|book| (book, highlight_density(book)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+/// The most recently added entry of each book, ties broken by the highest
+/// location. Books are returned in title order.
+pub fn latest_per_book(entries: &[Entry]) -> Vec<&Entry> {
+    let mut latest: BTreeMap<(&str, &str), &Entry> = BTreeMap::new();
+    for entry in entries {
+        latest
+            .entry((entry.title(), entry.author()))
+            .and_modify(|best| {
+                if recency(entry) > recency(best) {
+                    *best = entry;
+                }
+            })
+            .or_insert(entry);
+    }
+    latest.into_values().collect()
+}
+
+fn recency(entry: &Entry) -> (chrono::NaiveDateTime, u64) {
+    (entry.creation_date(), entry.position().start())
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "tokio")]
+
+use clippings_parser::{parse_file, parse_file_async, ParseError};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[tokio::test]
+async fn async_parsing_matches_the_blocking_path() {
+    let path = fixture("crlf.txt");
+
+    let entries = parse_file_async(&path).await.unwrap();
+
+    let blocking = parse_file(&path).unwrap();
+    assert_eq!(entries.len(), blocking.len());
+    for (entry, expected) in entries.iter().zip(&blocking) {
+        assert_eq!(entry.to_block_string(), expected.to_block_string());
+    }
+    assert_eq!(entries[1].source_offset(), Some(150));
+}
+
+#[tokio::test]
+async fn async_errors_name_the_file() {
+    let err = parse_file_async(fixture("missing.txt")).await.unwrap_err();
+    assert!(matches!(err, ParseError::FileOpen { .. }));
+
+    let err = parse_file_async(fixture("truncated.txt"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ParseError::TextNotFound));
+}
@@ -0,0 +1,69 @@
+use clippings_parser::{parse_bytes, parse_bytes_lossy, parse_file, parse_reader, ParseError};
+
+const CLIPPING: &str = "Dune (Frank Herbert)
+- Your Highlight on page 12 | Location 180-181 | Added on Monday, March 6, 2023 9:14:02 PM
+
+Fear is the mind-killer.
+==========
+";
+
+#[test]
+fn parses_valid_bytes() {
+    let entries = parse_bytes(CLIPPING.as_bytes()).unwrap();
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].text(), "Fear is the mind-killer.");
+}
+
+#[test]
+fn invalid_utf8_reports_where_it_starts() {
+    let mut bytes = CLIPPING.as_bytes().to_vec();
+    let at = CLIPPING.find("mind").unwrap();
+    bytes[at] = 0xff;
+
+    match parse_bytes(&bytes) {
+        Err(ParseError::InvalidUtf8 { valid_up_to }) => assert_eq!(valid_up_to, at),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_utf8_is_reported_the_same_when_read() {
+    let mut bytes = CLIPPING.as_bytes().to_vec();
+    let at = CLIPPING.find("mind").unwrap();
+    bytes[at] = 0xff;
+    let path = std::env::temp_dir().join(format!("invalid-utf8-{}.txt", std::process::id()));
+    std::fs::write(&path, &bytes).unwrap();
+
+    let from_file = parse_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    for result in [from_file, parse_reader(bytes.as_slice())] {
+        match result {
+            Err(ParseError::InvalidUtf8 { valid_up_to }) => assert_eq!(valid_up_to, at),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn lossy_replaces_invalid_utf8() {
+    let mut bytes = CLIPPING.as_bytes().to_vec();
+    let at = CLIPPING.find("mind").unwrap();
+    bytes[at] = 0xff;
+
+    let entries = parse_bytes_lossy(&bytes).unwrap();
+
+    assert_eq!(entries[0].text(), "Fear is the \u{fffd}ind-killer.");
+}
+
+#[test]
+fn reader_surfaces_invalid_utf8_instead_of_stopping() {
+    let mut bytes = CLIPPING.repeat(2).into_bytes();
+    let at = CLIPPING.len() + CLIPPING.find("mind").unwrap();
+    bytes[at] = 0xff;
+
+    match parse_reader(&bytes[..]) {
+        Err(ParseError::InvalidUtf8 { valid_up_to }) => assert_eq!(valid_up_to, at),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
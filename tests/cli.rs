@@ -0,0 +1,412 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn command(args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_clippings_parser"));
+    command
+        .args(args)
+        .env_remove("RUST_LOG")
+        .env_remove("CLIPPINGS_FILE");
+    command
+}
+
+fn run(args: &[&str]) -> Output {
+    command(args).output().unwrap()
+}
+
+#[test]
+fn validate_is_silent_on_a_good_file() {
+    let output = run(&["validate", "-c", &fixture("colored.txt")]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn validate_reports_the_failing_line() {
+    let output = run(&["validate", "-c", &fixture("truncated.txt")]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.ends_with("truncated.txt: line 6: Text is not found, the entry is truncated\n"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn preserve_order_keeps_input_order_after_filtering() {
+    let output = run(&[
+        "-q",
+        "-c",
+        &fixture("unordered.txt"),
+        "--preserve-order",
+        "--kind",
+        "highlight",
+        "-f",
+        "text",
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Tell me of the man of many ways.\n\nFear is the mind-killer.\n\n\
+         Sing to me of the man, Muse.\n\nBoredom is a mask.\n"
+    );
+}
+
+#[test]
+fn preserve_order_rejects_sorting() {
+    let output = run(&["--preserve-order", "--group-sort", "author"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    for command in ["books", "stats"] {
+        let output = run(&["-c", &fixture("unordered.txt"), "--preserve-order", command]);
+
+        assert_eq!(output.status.code(), Some(2), "{}", command);
+        assert!(output.stdout.is_empty(), "{}", command);
+    }
+}
+
+#[test]
+fn warnings_are_logged_only_when_rust_log_is_set() {
+    let args = ["-q", "-c", &fixture("doubled_separators.txt")];
+    let quiet = run(&args);
+    let logged = command(&args).env("RUST_LOG", "warn").output().unwrap();
+
+    assert!(quiet.stderr.is_empty());
+    let stderr = String::from_utf8(logged.stderr).unwrap();
+    assert!(
+        stderr.contains("line 12: skipping empty block"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn merge_writes_one_canonical_file() {
+    let output = std::env::temp_dir().join(format!("merged-{}.txt", std::process::id()));
+    let devices = fixture("devices");
+    let result = run(&[
+        "-q",
+        "-c",
+        &format!("{}/kindle-2022/notes.txt", devices),
+        "-c",
+        &format!("{}/kindle-2022/backup/My Clippings.txt", devices),
+        "-c",
+        &format!("{}/kindle-2021/My Clippings.txt", devices),
+        "merge",
+        "--output",
+        output.to_str().unwrap(),
+    ]);
+
+    assert!(result.status.success());
+    let merged = clippings_parser::parse_file(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    let order: Vec<(&str, u64, Option<&str>)> = merged
+        .iter()
+        .map(|entry| {
+            (
+                entry.title(),
+                entry.location().unwrap().start(),
+                entry.color(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            ("Dune", 2000, None),
+            ("The Odyssey", 100, Some("yellow")),
+            ("The Odyssey", 100, None),
+            ("The Odyssey", 102, None),
+            ("The Odyssey", 110, Some("blue")),
+            ("The Odyssey", 120, None),
+        ]
+    );
+}
+
+#[test]
+fn timing_is_reported_on_request() {
+    let quiet = run(&["-q", "--count", "-c", &fixture("colored.txt")]);
+    assert!(quiet.stderr.is_empty());
+
+    let output = run(&["-q", "--timing", "--count", "-c", &fixture("colored.txt")]);
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("parsed 3 entries in "), "{}", stderr);
+    assert!(stderr.ends_with(" entries/s)\n"), "{}", stderr);
+}
+
+#[test]
+fn exit_status_reflects_the_parse_outcome() {
+    let ok = run(&["-q", "--count", "-c", &fixture("colored.txt")]);
+    assert_eq!(ok.status.code(), Some(0));
+
+    let failed = run(&["-q", "--count", "-c", &fixture("truncated.txt")]);
+    assert_eq!(failed.status.code(), Some(1));
+    assert!(failed.stdout.is_empty());
+
+    let partial = run(&[
+        "-q",
+        "--lenient",
+        "--count",
+        "-c",
+        &fixture("truncated.txt"),
+    ]);
+    assert_eq!(partial.status.code(), Some(2));
+    assert_eq!(String::from_utf8(partial.stdout).unwrap(), "1\n");
+}
+
+#[test]
+fn input_comes_from_the_argument_then_the_environment_then_stdin() {
+    let count = |output: Output| String::from_utf8(output.stdout).unwrap();
+
+    let from_env = command(&["-q", "--count"])
+        .env("CLIPPINGS_FILE", fixture("colored.txt"))
+        .output()
+        .unwrap();
+    assert_eq!(count(from_env), "3\n");
+
+    let from_arg = command(&[
+        "-q",
+        "--count",
+        "-c",
+        &fixture("truncated.txt"),
+        "--lenient",
+    ])
+    .env("CLIPPINGS_FILE", fixture("colored.txt"))
+    .output()
+    .unwrap();
+    assert_eq!(count(from_arg), "1\n");
+
+    let mut child = command(&["-q", "--count"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&std::fs::read(fixture("pronouns.txt")).unwrap())
+        .unwrap();
+    assert_eq!(count(child.wait_with_output().unwrap()), "4\n");
+}
+
+#[test]
+fn fast_count_skips_parsing() {
+    let output = run(&["-q", "--count", "--fast", "-c", &fixture("truncated.txt")]);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "2\n");
+}
+
+#[test]
+fn meta_regex_is_checked_before_parsing() {
+    let output = run(&[
+        "-q",
+        "--count",
+        "-c",
+        &fixture("colored.txt"),
+        "--meta-regex",
+        r"^- (?P<kind>\S+) (?P<date>.*)$",
+    ]);
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("missing the named group `page`"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn diff_prints_added_and_removed_sections() {
+    let devices = fixture("devices");
+    let output = run(&[
+        "-q",
+        "--kind",
+        "highlight",
+        "diff",
+        &format!("{}/kindle-2021/My Clippings.txt", devices),
+        &format!("{}/kindle-2022/notes.txt", devices),
+    ]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let sections: Vec<&str> = stdout
+        .lines()
+        .filter(|line| !line.starts_with("  "))
+        .collect();
+    assert_eq!(sections, vec!["added: 3", "removed: 1"]);
+    assert_eq!(stdout.lines().count(), 6);
+}
+
+#[test]
+fn verbose_reports_format_variants() {
+    let path = fixture("pronouns.txt");
+    let output = run(&["-q", "-v", "--count", "-c", &path]);
+
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "{}: format variants: clipping-kind 1, my-prefix 2, no-prefix 1\n",
+            path
+        )
+    );
+}
+
+#[test]
+fn merge_reports_an_unwritable_output() {
+    let output = std::env::temp_dir()
+        .join(format!("missing-{}", std::process::id()))
+        .join("merged.txt");
+    let result = run(&[
+        "-q",
+        "-c",
+        &fixture("colored.txt"),
+        "merge",
+        "--output",
+        output.to_str().unwrap(),
+    ]);
+
+    assert_eq!(result.status.code(), Some(1));
+    let stderr = String::from_utf8(result.stderr).unwrap();
+    assert!(
+        stderr.starts_with(&format!("{}: ", output.display())),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn output_errors_are_reported_instead_of_panicking() {
+    let colored = fixture("colored.txt");
+    for args in [
+        &["-q", "-f", "clippings", "-c", &colored][..],
+        &["-q", "--count", "-c", &colored],
+        &["-q", "-c", &colored, "books"],
+        &["-q", "-c", &colored, "stats"],
+    ] {
+        let full = std::fs::OpenOptions::new()
+            .write(true)
+            .open("/dev/full")
+            .unwrap();
+        let output = command(args).stdout(full).output().unwrap();
+
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(
+            stderr.starts_with("cannot write the output: "),
+            "{:?}: {}",
+            args,
+            stderr
+        );
+    }
+}
+
+#[test]
+fn garbage_state_file_is_reported() {
+    let state = std::env::temp_dir().join(format!("state-{}.txt", std::process::id()));
+    std::fs::write(&state, "not a date\n").unwrap();
+
+    let output = run(&[
+        "-q",
+        "--count",
+        "-c",
+        &fixture("colored.txt"),
+        "--newer-than-file",
+        state.to_str().unwrap(),
+    ]);
+    std::fs::remove_file(&state).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.starts_with(&format!("{}: Invalid date", state.display())),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn diff_ignores_filters_that_change_entries() {
+    let colored = fixture("colored.txt");
+    let output = run(&[
+        "-q", "--redact", "--random", "1", "diff", &colored, &colored,
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "added: 0\nremoved: 0\n"
+    );
+}
+
+#[test]
+fn diff_exits_with_the_parse_outcome() {
+    let output = run(&[
+        "-q",
+        "--lenient",
+        "diff",
+        &fixture("colored.txt"),
+        &fixture("truncated.txt"),
+    ]);
+
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("removed: 3"), "{}", stdout);
+}
+
+#[test]
+fn fast_count_reports_a_missing_input() {
+    let missing = fixture("missing.txt");
+    let fast = run(&["-q", "--count", "--fast", "-c", &missing]);
+    let slow = run(&["-q", "--count", "-c", &missing]);
+
+    assert_eq!(fast.status.code(), Some(1));
+    assert_eq!(fast.stderr, slow.stderr);
+    assert!(String::from_utf8(fast.stderr)
+        .unwrap()
+        .starts_with(&format!("{}: Cannot open", missing)));
+}
+
+#[test]
+fn stats_count_every_revised_highlight() {
+    let output = run(&["-q", "-c", &fixture("revised.txt"), "stats"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\nrevised highlights: 3\n"), "{}", stdout);
+}
+
+#[test]
+fn verbose_says_variants_are_unavailable_with_a_custom_regex() {
+    let path = fixture("custom_meta.txt");
+    let output = run(&[
+        "-q",
+        "-v",
+        "--count",
+        "-c",
+        &path,
+        "--meta-regex",
+        r"^\* (?P<kind>\w+)( \((?P<color>\w+)\))?( p\.(?P<page>\d+))?( loc\.(?P<location>[\d-]+))? @ (?P<date>.*)$",
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("{}: format variants: unavailable with --meta-regex\n", path)
+    );
+}
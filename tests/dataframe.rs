@@ -0,0 +1,47 @@
+#![cfg(feature = "polars")]
+
+use clippings_parser::dataframe::to_dataframe;
+use clippings_parser::parse_file;
+use polars::prelude::{DataType, TimeUnit};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn frame_has_a_row_per_entry_and_a_column_per_field() {
+    let entries = parse_file(fixture("colored.txt")).unwrap();
+
+    let frame = to_dataframe(&entries).unwrap();
+
+    assert_eq!(frame.shape(), (3, 9));
+    let names: Vec<&str> = frame
+        .get_column_names()
+        .into_iter()
+        .map(|name| name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "title",
+            "author",
+            "kind",
+            "color",
+            "page",
+            "location_start",
+            "location_end",
+            "creation_date",
+            "text"
+        ]
+    );
+    let creation_date = frame.column("creation_date").unwrap();
+    assert_eq!(
+        creation_date.dtype(),
+        &DataType::Datetime(TimeUnit::Milliseconds, None)
+    );
+    assert_eq!(
+        frame.column("location_start").unwrap().dtype(),
+        &DataType::UInt64
+    );
+    assert_eq!(frame.column("color").unwrap().null_count(), 1);
+}
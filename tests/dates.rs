@@ -0,0 +1,48 @@
+use chrono::NaiveDate;
+use clippings_parser::{parse_file, parse_file_lenient, DatePrecision, ParseWarning};
+
+const MIXED_LOCALE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/mixed_locale.txt"
+);
+
+#[test]
+fn date_label_may_be_in_another_language() {
+    let entries = parse_file(MIXED_LOCALE).unwrap();
+
+    let dates: Vec<_> = entries.iter().map(|entry| entry.creation_date()).collect();
+    assert_eq!(
+        dates,
+        vec![
+            NaiveDate::from_ymd_opt(2022, 1, 3)
+                .unwrap()
+                .and_hms_opt(10, 15, 30)
+                .unwrap(),
+            NaiveDate::from_ymd_opt(2022, 1, 4)
+                .unwrap()
+                .and_hms_opt(19, 5, 0)
+                .unwrap(),
+        ]
+    );
+}
+
+const DATE_ONLY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/date_only.txt");
+
+#[test]
+fn date_without_time_is_midnight_and_reported() {
+    let entries = parse_file(DATE_ONLY).unwrap();
+
+    assert_eq!(
+        entries[0].creation_date(),
+        NaiveDate::from_ymd_opt(2022, 1, 3)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    );
+    assert_eq!(entries[0].date_precision(), DatePrecision::Date);
+    assert_eq!(entries[1].date_precision(), DatePrecision::DateTime);
+
+    let report = parse_file_lenient(DATE_ONLY).unwrap();
+    assert!(report.errors.is_empty());
+    assert_eq!(report.warnings, vec![(1, ParseWarning::DateWithoutTime)]);
+}
@@ -0,0 +1,27 @@
+use clippings_parser::discover::find_clippings_files;
+use std::path::{Path, PathBuf};
+
+fn devices() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/devices")
+}
+
+#[test]
+fn finds_clippings_files_recursively() {
+    let files = find_clippings_files(&devices(), false).unwrap();
+
+    assert_eq!(
+        files,
+        vec![
+            devices().join("kindle-2021/My Clippings.txt"),
+            devices().join("kindle-2022/backup/My Clippings.txt"),
+        ]
+    );
+}
+
+#[test]
+fn any_txt_includes_other_text_files() {
+    let files = find_clippings_files(&devices(), true).unwrap();
+
+    assert_eq!(files.len(), 3);
+    assert!(files.contains(&devices().join("kindle-2022/notes.txt")));
+}
@@ -0,0 +1,26 @@
+use clippings_parser::file_parser::validate_date_format;
+use clippings_parser::parse_reader;
+
+const HIGHLIGHT: &str = "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+";
+
+#[test]
+fn custom_date_format_is_used_for_display() {
+    let entries = parse_reader(HIGHLIGHT.as_bytes()).unwrap();
+
+    let rendered = entries[0].display_with_date_format("%d.%m.%Y").to_string();
+    assert!(rendered.starts_with("Highlight (03.01.2022) in Homer"));
+}
+
+#[test]
+fn unknown_strftime_items_are_rejected() {
+    assert!(validate_date_format("%d.%m.%Y %H:%M").is_ok());
+    assert!(validate_date_format("%Q").is_err());
+    assert!(validate_date_format("%Z").is_err());
+    assert!(validate_date_format("%d %z").is_err());
+}
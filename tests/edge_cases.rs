@@ -0,0 +1,101 @@
+use clippings_parser::{
+    count_blocks, count_entries, parse_file, parse_file_lenient, parse_reader_iter,
+    parse_reader_lenient, parse_str, Entry,
+};
+use std::fs::File;
+use std::io::BufReader;
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn zero_byte_file_is_empty() {
+    assert!(parse_file(fixture("empty.txt")).unwrap().is_empty());
+}
+
+#[test]
+fn separator_and_whitespace_only_file_is_empty() {
+    assert!(parse_file(fixture("whitespace_only.txt"))
+        .unwrap()
+        .is_empty());
+
+    let report = parse_reader_lenient("  \n==========\n\t\n".as_bytes());
+    assert!(report.entries.is_empty());
+    assert!(report.errors.is_empty());
+}
+
+#[test]
+fn malformed_blocks_are_errors_not_panics() {
+    let inputs = [
+        "just one line\n==========\n",
+        "Title (Author)\n==========\n",
+        "no author here\n- Your Highlight on Location 1 | Added on Monday, January 3, 2022 10:15:30 AM\n\ntext\n",
+        "Title (Author)\n- something else entirely\n\ntext\n",
+        "Title (Author)\n- Your Highlight on Location 99999999999999999999 | Added on x\n\ntext\n",
+        "Title (Author)\n- Your Highlight on Location 1-2-3 | Added on Monday, January 3, 2022 10:15:30 AM\n\ntext\n",
+        "Title (Author)\n- Your Highlight on Location 1 | Added on Monday, January 3, 2022 10:15:30 AM\n",
+        "Title (Author)\n- Your Highlight on Location 1 | \u{1F600} \u{1F600}\n\ntext\n",
+    ];
+    for input in inputs {
+        assert!(parse_str(input).is_err(), "{:?} should not parse", input);
+        assert_eq!(parse_reader_lenient(input.as_bytes()).errors.len(), 1);
+    }
+}
+
+#[test]
+fn doubled_separators_are_skipped() {
+    let entries = parse_file(fixture("doubled_separators.txt")).unwrap();
+
+    let texts: Vec<&str> = entries.iter().map(|entry| entry.text()).collect();
+    assert_eq!(
+        texts,
+        vec![
+            "Fear is the mind-killer.",
+            "I must not fear.",
+            "Great opening"
+        ]
+    );
+
+    let report = parse_file_lenient(fixture("doubled_separators.txt")).unwrap();
+    assert_eq!(report.entries.len(), 3);
+    assert!(report.errors.is_empty());
+}
+
+#[test]
+fn last_entry_without_trailing_separator_is_kept() {
+    let entries = parse_file(fixture("no_trailing_separator.txt")).unwrap();
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1].text(), "I must not fear.");
+
+    let contents = std::fs::read_to_string(fixture("no_trailing_separator.txt")).unwrap();
+    for input in [contents.clone(), format!("{}\n", contents)] {
+        let entries = parse_str(&input).unwrap();
+        assert_eq!(entries.last().unwrap().text(), "I must not fear.");
+        assert_eq!(parse_reader_lenient(input.as_bytes()).entries.len(), 2);
+    }
+}
+
+#[test]
+fn lazy_iterator_yields_the_same_entries() {
+    let path = fixture("doubled_separators.txt");
+    let lazy: Vec<Entry> = parse_reader_iter(BufReader::new(File::open(&path).unwrap()))
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(lazy, parse_file(&path).unwrap());
+}
+
+#[test]
+fn entries_are_counted_without_collecting() {
+    let doubled = std::fs::read(fixture("doubled_separators.txt")).unwrap();
+    let expected = parse_file(fixture("doubled_separators.txt")).unwrap().len();
+
+    assert_eq!(count_entries(&doubled[..]).unwrap(), expected);
+    assert_eq!(count_blocks(&doubled[..]).unwrap(), expected);
+
+    let truncated = std::fs::read(fixture("truncated.txt")).unwrap();
+    assert!(count_entries(&truncated[..]).is_err());
+    assert_eq!(count_blocks(&truncated[..]).unwrap(), 2);
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "encoding")]
+
+use clippings_parser::encoding::{decode, parse_bytes_with_encoding, InputEncoding};
+use clippings_parser::parse_bytes;
+use std::fs;
+
+fn fixture(name: &str) -> Vec<u8> {
+    fs::read(format!("tests/fixtures/{}", name)).unwrap()
+}
+
+#[test]
+fn windows_1252_is_not_utf8() {
+    assert!(parse_bytes(&fixture("windows1252.txt")).is_err());
+}
+
+#[test]
+fn detects_windows_1252() {
+    let entries =
+        parse_bytes_with_encoding(&fixture("windows1252.txt"), InputEncoding::Detect).unwrap();
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].title(), "L'Étranger");
+    assert_eq!(
+        entries[0].text(),
+        "Aujourd'hui, maman est morte. Ou peut-être hier, je ne sais pas."
+    );
+    assert_eq!(entries[1].author(), "Gottfried Bürger");
+    assert_eq!(entries[1].text(), "Café crème — déjà vu");
+}
+
+#[test]
+fn explicit_label_overrides_detection() {
+    let encoding: InputEncoding = "latin1".parse().unwrap();
+    let entries = parse_bytes_with_encoding(&fixture("windows1252.txt"), encoding).unwrap();
+
+    assert_eq!(entries[1].title(), "Münchhausen");
+}
+
+#[test]
+fn utf8_input_with_bom_is_detected() {
+    let mut bytes = b"\xef\xbb\xbf".to_vec();
+    bytes.extend_from_slice("Déjà".as_bytes());
+
+    assert_eq!(decode(&bytes, InputEncoding::Detect), "Déjà");
+}
+
+#[test]
+fn unknown_label_is_rejected() {
+    assert!("klingon".parse::<InputEncoding>().is_err());
+}
@@ -0,0 +1,25 @@
+use clippings_parser::{parse_file, ParseError};
+use std::path::Path;
+
+#[test]
+fn missing_file_error_names_the_path() {
+    let err = parse_file("does/not/exist.txt").err().unwrap();
+
+    match &err {
+        ParseError::FileOpen { path, .. } => assert_eq!(path, Path::new("does/not/exist.txt")),
+        other => panic!("unexpected error {:?}", other),
+    }
+    assert_eq!(err.to_string(), "Cannot open does/not/exist.txt");
+}
+
+fn assert_error_bounds<E: std::error::Error + Send + Sync + 'static>() {}
+
+#[test]
+fn errors_can_cross_threads_and_be_boxed() {
+    assert_error_bounds::<ParseError>();
+
+    fn count(path: &str) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(parse_file(path)?.len())
+    }
+    assert!(count("does/not/exist.txt").is_err());
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "serde")]
+
+use clippings_parser::export::{
+    to_csv, to_json, to_json_envelope, to_tsv_with_columns, write_json_stream, Column,
+};
+use clippings_parser::schema::{Envelope, SCHEMA_VERSION};
+use clippings_parser::{parse_reader, parse_reader_iter, EntryType, ParseError};
+
+const ODYSSEY: &str = "\
+The Odyssey (Homer)
+- Your Highlight on page 5 | Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+The Odyssey (Homer)
+- Your Bookmark on Location 150 | Added on Monday, January 3, 2022 10:17:00 AM
+
+
+==========
+";
+
+#[test]
+fn csv_has_every_column_but_id_by_default() {
+    let entries = parse_reader(ODYSSEY.as_bytes()).unwrap();
+
+    assert_eq!(
+        to_csv(&entries).unwrap(),
+        "\
+title,author,kind,page,location_start,location_end,creation_date,text
+The Odyssey,Homer,Highlight,5,100,102,2022-01-03T10:15:30,\"Sing to me of the man, Muse.\"
+The Odyssey,Homer,Bookmark,,150,150,2022-01-03T10:17:00,
+"
+    );
+}
+
+#[test]
+fn columns_are_selected_and_ordered() {
+    let entries = parse_reader(ODYSSEY.as_bytes()).unwrap();
+    let columns: Vec<Column> = "kind, id,page"
+        .split(',')
+        .map(|name| name.parse().unwrap())
+        .collect();
+
+    let tsv = to_tsv_with_columns(&entries, &columns).unwrap();
+
+    let rows: Vec<&str> = tsv.lines().collect();
+    assert_eq!(rows[0], "kind\tid\tpage");
+    assert_eq!(rows[1], format!("Highlight\t{}\t5", entries[0].id()));
+    assert_eq!(rows[2], format!("Bookmark\t{}\t", entries[1].id()));
+}
+
+#[test]
+fn ids_are_stable_and_distinct() {
+    let first = parse_reader(ODYSSEY.as_bytes()).unwrap();
+    let second = parse_reader(ODYSSEY.as_bytes()).unwrap();
+
+    assert_eq!(first[0].id(), second[0].id());
+    assert_ne!(first[0].id(), first[1].id());
+    assert_eq!(first[0].id().len(), 16);
+}
+
+#[test]
+fn unknown_columns_are_named_in_the_error() {
+    let err = "titel".parse::<Column>().unwrap_err();
+
+    assert!(matches!(&err, ParseError::UnknownColumn(name) if name == "titel"));
+    assert!(err.to_string().contains("title, author, kind"));
+}
+
+#[test]
+fn streamed_json_matches_the_buffered_export() {
+    let mut out = vec![];
+    write_json_stream(parse_reader_iter(ODYSSEY.as_bytes()), &mut out).unwrap();
+
+    let streamed: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    let buffered: serde_json::Value =
+        serde_json::from_str(&to_json(&parse_reader(ODYSSEY.as_bytes()).unwrap()).unwrap())
+            .unwrap();
+    assert_eq!(streamed, buffered);
+
+    let mut out = vec![];
+    write_json_stream(parse_reader_iter("".as_bytes()), &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "[\n]\n");
+}
+
+#[test]
+fn streamed_json_stops_at_the_first_parse_error() {
+    let truncated = format!("{}Dune (Frank Herbert)\n==========\n", ODYSSEY);
+    let mut out = vec![];
+
+    let err = write_json_stream(parse_reader_iter(truncated.as_bytes()), &mut out).unwrap_err();
+
+    assert!(matches!(err, ParseError::MalformedEntry(_)));
+    assert!(String::from_utf8(out).unwrap().contains("Sing to me"));
+}
+
+#[test]
+fn envelope_wraps_the_bare_array_with_a_version() {
+    let entries = parse_reader(ODYSSEY.as_bytes()).unwrap();
+
+    let json = to_json_envelope(&entries).unwrap();
+
+    let envelope: Envelope = serde_json::from_str(&json).unwrap();
+    assert_eq!(envelope.version, SCHEMA_VERSION);
+    assert_eq!(envelope.entries[1].kind, EntryType::Bookmark);
+    let wrapped: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let bare: serde_json::Value = serde_json::from_str(&to_json(&entries).unwrap()).unwrap();
+    assert_eq!(wrapped["entries"], bare);
+}
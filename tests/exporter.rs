@@ -0,0 +1,73 @@
+use clippings_parser::exporter::{
+    ClippingsExporter, DebugExporter, Exporter, LineExporter, Registry, TextExporter,
+};
+use clippings_parser::render::{to_clippings, to_text};
+use clippings_parser::{parse_str, Entry, ParseError};
+use std::io::Write;
+
+const ODYSSEY: &str = "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+The Odyssey (Homer)
+- Your Bookmark on Location 150 | Added on Monday, January 3, 2022 10:17:00 AM
+
+
+==========
+";
+
+fn export(exporter: &dyn Exporter, entries: &[Entry]) -> String {
+    let mut out = vec![];
+    exporter.export(entries, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn built_in_exporters_match_the_render_functions() {
+    let entries = parse_str(ODYSSEY).unwrap();
+
+    assert_eq!(
+        export(&ClippingsExporter, &entries),
+        to_clippings(&entries).unwrap()
+    );
+    let text = TextExporter {
+        include_bookmarks: false,
+    };
+    assert_eq!(export(&text, &entries), to_text(&entries));
+    assert_eq!(
+        export(&LineExporter { width: 60 }, &entries)
+            .lines()
+            .count(),
+        2
+    );
+    let debug = DebugExporter {
+        include_bookmarks: false,
+        date_format: Some("%Y-%m-%d".to_string()),
+        escape_control: false,
+    };
+    assert_eq!(
+        export(&debug, &entries),
+        format!("{}\n", entries[0].display_with_date_format("%Y-%m-%d"))
+    );
+}
+
+#[test]
+fn registry_finds_exporters_by_name() {
+    let mut registry = Registry::new();
+    registry.register("clippings", ClippingsExporter).register(
+        "count",
+        |entries: &[Entry], writer: &mut dyn Write| {
+            Ok::<_, ParseError>(writeln!(writer, "{}", entries.len())?)
+        },
+    );
+
+    assert_eq!(
+        registry.names().collect::<Vec<_>>(),
+        vec!["clippings", "count"]
+    );
+    assert!(registry.get("csv").is_none());
+    let entries = parse_str(ODYSSEY).unwrap();
+    assert_eq!(export(registry.get("count").unwrap(), &entries), "2\n");
+}
@@ -0,0 +1,414 @@
+use chrono::NaiveDate;
+use clippings_parser::filters::{
+    collapse_by_location, dedup, dedup_fuzzy_text, dedup_normalized, diff, filter_by_author,
+    filter_by_book, filter_by_book_contains, filter_by_date, filter_by_kind, filter_by_location,
+    filter_min_length, filter_min_length_all, keep_latest, redact_text, sample_highlights,
+    sort_dedup, EntryIterExt,
+};
+use clippings_parser::{parse_reader, Entry, EntryType, Location};
+use std::collections::HashSet;
+
+const EDITED: &str = "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:20:00 AM
+
+Fear is the mind-killer.
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Tuesday, January 4, 2022 8:00:00 AM
+
+Sing to me of the man, Muse.
+==========
+";
+
+#[test]
+fn keep_latest_keeps_the_newest_edit_per_location() {
+    let entries = keep_latest(parse_reader(EDITED.as_bytes()).unwrap());
+
+    let texts: Vec<&str> = entries.iter().map(|entry| entry.text()).collect();
+    assert_eq!(
+        texts,
+        vec!["Fear is the mind-killer.", "Sing to me of the man, Muse."]
+    );
+}
+
+const MIXED: &str = "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+The Odyssey (Homer)
+- Your Note on Location 102 | Added on Wednesday, January 5, 2022 10:16:00 AM
+
+Great opening
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 200-201 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Fear is the mind-killer.
+==========
+";
+
+#[test]
+fn author_kind_and_date_filters_compose() {
+    let entries = parse_reader(MIXED.as_bytes()).unwrap();
+    let entries = filter_by_author(entries, "homer");
+    let entries = filter_by_date(
+        entries,
+        NaiveDate::from_ymd_opt(2022, 1, 4),
+        NaiveDate::from_ymd_opt(2022, 1, 31),
+    );
+    let entries = filter_by_kind(entries, EntryType::Note);
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].text(), "Great opening");
+}
+
+#[test]
+fn iterator_adapters_chain_lazily() {
+    let entries = parse_reader(MIXED.as_bytes()).unwrap();
+
+    let texts: Vec<&str> = entries
+        .iter()
+        .highlights()
+        .by_author("HERBERT")
+        .in_range(NaiveDate::from_ymd_opt(2022, 2, 1), None)
+        .map(|entry| entry.text())
+        .collect();
+
+    assert_eq!(texts, vec!["Fear is the mind-killer."]);
+}
+
+#[test]
+fn min_length_counts_characters_and_spares_notes() {
+    let entries = parse_reader(
+        "\
+Der Prozess (Franz Kafka)
+- Your Highlight on Location 10-11 | Added on Monday, January 3, 2022 10:15:30 AM
+
+  Schön  
+==========
+Der Prozess (Franz Kafka)
+- Your Highlight on Location 12-13 | Added on Monday, January 3, 2022 10:16:30 AM
+
+Jemand mußte Josef K. verleumdet haben.
+==========
+Der Prozess (Franz Kafka)
+- Your Note on Location 13 | Added on Monday, January 3, 2022 10:17:30 AM
+
+ok
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    let kept = filter_min_length(entries.clone(), 5);
+    let texts: Vec<&str> = kept.iter().map(|entry| entry.text()).collect();
+    assert_eq!(
+        texts,
+        vec!["Schön  ", "Jemand mußte Josef K. verleumdet haben.", "ok"]
+    );
+
+    let kept = filter_min_length(entries.clone(), 6);
+    assert_eq!(kept.len(), 2);
+
+    let kept = filter_min_length_all(entries, 6);
+    assert_eq!(kept.len(), 1);
+}
+
+#[test]
+fn seeded_sample_is_reproducible() {
+    let entries = parse_reader(MIXED.as_bytes()).unwrap();
+
+    let first = sample_highlights(entries.clone(), 1, Some(7));
+    let second = sample_highlights(entries.clone(), 1, Some(7));
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].text(), second[0].text());
+    assert_eq!(*first[0].kind(), EntryType::Highlight);
+
+    let all = sample_highlights(entries, 10, None);
+    assert_eq!(all.len(), 2);
+}
+
+#[test]
+fn book_filter_matches_title_exactly_unless_asked_for_substring() {
+    let library = format!(
+        "{}\
+Dune (Brian Herbert)
+- Your Highlight on Location 5-6 | Added on Monday, February 7, 2022 9:30:00 PM
+
+A different Dune.
+==========
+",
+        MIXED
+    );
+    let entries = parse_reader(library.as_bytes()).unwrap();
+
+    let dune = filter_by_book(entries.clone(), "Dune");
+    let authors: Vec<&str> = dune.iter().map(|entry| entry.author()).collect();
+    assert_eq!(authors, vec!["Frank Herbert", "Brian Herbert"]);
+    assert!(filter_by_book(entries.clone(), "dune").is_empty());
+    assert_eq!(filter_by_book_contains(entries, "odys").len(), 2);
+}
+
+#[test]
+fn dedup_drops_repeats_regardless_of_source() {
+    let mut entries = parse_reader(MIXED.as_bytes()).unwrap();
+    entries.extend(parse_reader(MIXED.as_bytes()).unwrap());
+    entries.extend(parse_reader(EDITED.as_bytes()).unwrap());
+
+    assert_eq!(entries.len(), 9);
+    let unique = dedup(entries);
+
+    let texts: Vec<&str> = unique.iter().map(|entry| entry.text()).collect();
+    assert_eq!(
+        texts,
+        vec![
+            "Sing to me of the man, Muse.",
+            "Great opening",
+            "Fear is the mind-killer.",
+            "Sing to me of the man",
+            "Fear is the mind-killer.",
+            "Sing to me of the man, Muse.",
+        ]
+    );
+}
+
+#[test]
+fn sort_dedup_keeps_the_same_entries_as_dedup() {
+    let mut entries = parse_reader(MIXED.as_bytes()).unwrap();
+    entries.extend(parse_reader(EDITED.as_bytes()).unwrap());
+    entries.extend(parse_reader(MIXED.as_bytes()).unwrap());
+
+    let hashed: HashSet<Entry> = dedup(entries.clone()).into_iter().collect();
+    let sorted = sort_dedup(entries);
+
+    assert_eq!(sorted.len(), hashed.len());
+    assert!(sorted.iter().all(|entry| hashed.contains(entry)));
+    assert_eq!(sorted[0].title(), "Dune");
+}
+
+#[test]
+fn normalized_dedup_ignores_title_case_and_spacing() {
+    let entries = parse_reader(
+        "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+THE  ODYSSEY (HOMER)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+    assert_eq!(dedup(entries.clone()).len(), 2);
+
+    let unique = dedup_normalized(entries);
+
+    assert_eq!(unique.len(), 1);
+    assert_eq!(
+        (unique[0].title(), unique[0].author()),
+        ("The Odyssey", "Homer")
+    );
+}
+
+#[test]
+fn fuzzy_dedup_keeps_the_longer_of_punctuation_variants() {
+    let entries = parse_reader(
+        "\
+Dune (Frank Herbert)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Fear is the mind-killer
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 20-21 | Added on Monday, February 7, 2022 9:01:00 PM
+
+I must not fear.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:02:00 PM
+
+Fear is the mind-killer.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 30-31 | Added on Monday, February 7, 2022 9:03:00 PM
+
+Fear is the mind-killer
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    let unique = dedup_fuzzy_text(entries);
+
+    let kept: Vec<(u64, &str)> = unique
+        .iter()
+        .map(|entry| (entry.location().unwrap().start(), entry.text()))
+        .collect();
+    assert_eq!(
+        kept,
+        vec![
+            (10, "Fear is the mind-killer."),
+            (20, "I must not fear."),
+            (30, "Fear is the mind-killer"),
+        ]
+    );
+}
+
+#[test]
+fn location_filter_keeps_overlapping_entries() {
+    let entries = parse_reader(
+        "\
+Dune (Frank Herbert)
+- Your Highlight on Location 100-105 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Inside.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 95-101 | Added on Monday, February 7, 2022 9:01:00 PM
+
+Across the start.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 150-160 | Added on Monday, February 7, 2022 9:02:00 PM
+
+Elsewhere.
+==========
+Dune (Frank Herbert)
+- Your Highlight on page 3 | Added on Monday, February 7, 2022 9:03:00 PM
+
+No location.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+    let chapter = Location::try_from((98, 120)).unwrap();
+
+    let texts: Vec<&str> = filter_by_location(&entries, &chapter)
+        .iter()
+        .map(|entry| entry.text())
+        .collect();
+
+    assert_eq!(texts, vec!["Inside.", "Across the start."]);
+    assert!(!chapter.overlaps(&Location::try_from((121, 130)).unwrap()));
+    assert!(chapter.overlaps(&Location::try_from((120, 130)).unwrap()));
+}
+
+#[test]
+fn redaction_hides_text_but_keeps_metadata() {
+    let entries = parse_reader(MIXED.as_bytes()).unwrap();
+
+    let redacted = redact_text(entries.clone());
+
+    for (before, after) in entries.iter().zip(&redacted) {
+        assert_eq!(
+            (
+                before.title(),
+                before.author(),
+                before.location(),
+                before.creation_date()
+            ),
+            (
+                after.title(),
+                after.author(),
+                after.location(),
+                after.creation_date()
+            )
+        );
+    }
+    assert_eq!(redacted[0].text(), "[redacted: 28 chars]");
+    assert!(redacted.iter().all(|entry| !entry.text().contains("Muse")));
+}
+
+#[test]
+fn diff_lists_entries_only_on_one_side() {
+    let old = parse_reader(EDITED.as_bytes()).unwrap();
+    let mut new = old[1..].to_vec();
+    new.extend(
+        parse_reader(
+            "\
+Dune (Frank Herbert)
+- Your Highlight on Location 200-201 | Added on Monday, February 7, 2022 9:00:00 PM
+
+I must not fear.
+==========
+"
+            .as_bytes(),
+        )
+        .unwrap(),
+    );
+    new.push(new[new.len() - 1].clone());
+
+    let changes = diff(&old, &new);
+
+    let added: Vec<&str> = changes.added.iter().map(|entry| entry.text()).collect();
+    assert_eq!(added, vec!["I must not fear."]);
+    assert_eq!(changes.removed, vec![&old[0]]);
+    assert!(diff(&old, &old).added.is_empty());
+}
+
+#[test]
+fn one_highlight_per_location_prefers_the_longest_then_the_latest() {
+    let entries = parse_reader(
+        "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-101 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man
+==========
+The Odyssey (Homer)
+- Your Note on Location 100 | Added on Monday, January 3, 2022 10:15:40 AM
+
+Kept.
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:16:00 AM
+
+Sing to me of the man, Muse
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:17:00 AM
+
+Sing to me of the man, muse
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 100-101 | Added on Monday, January 3, 2022 10:18:00 AM
+
+Sing to me of the man
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 100-101 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Fear is the mind-killer.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+    let collapsed = collapse_by_location(entries);
+
+    let texts: Vec<&str> = collapsed.iter().map(|entry| entry.text()).collect();
+    assert_eq!(
+        texts,
+        vec![
+            "Kept.",
+            "Sing to me of the man, muse",
+            "Fear is the mind-killer."
+        ]
+    );
+}
@@ -0,0 +1,164 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use clippings_parser::{parse_file, Entry, EntryType, Location, Page};
+
+/// Every field of a parsed entry, so a fixture is checked in one comparison.
+#[derive(Debug, PartialEq)]
+struct Golden<'a> {
+    title: &'a str,
+    author: &'a str,
+    kind: EntryType,
+    page: Option<Page>,
+    location: Option<Location>,
+    creation_date: NaiveDateTime,
+    text: &'a str,
+}
+
+impl<'a> From<&'a Entry> for Golden<'a> {
+    fn from(entry: &'a Entry) -> Self {
+        Golden {
+            title: entry.title(),
+            author: entry.author(),
+            kind: *entry.kind(),
+            page: entry.page().copied(),
+            location: entry.location().copied(),
+            creation_date: entry.creation_date(),
+            text: entry.text(),
+        }
+    }
+}
+
+fn assert_golden(name: &str, expected: Golden) {
+    let path = format!(
+        "{}/tests/fixtures/golden/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    let entries = parse_file(path).unwrap();
+
+    let parsed: Vec<Golden> = entries.iter().map(Golden::from).collect();
+    assert_eq!(parsed, vec![expected], "{}", name);
+}
+
+fn location(start: u64, end: u64) -> Option<Location> {
+    Some(Location::try_from((start, end)).unwrap())
+}
+
+fn date(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(y, m, d)
+        .unwrap()
+        .and_hms_opt(h, min, s)
+        .unwrap()
+}
+
+#[test]
+fn highlight() {
+    assert_golden(
+        "highlight.txt",
+        Golden {
+            title: "The Odyssey",
+            author: "Homer",
+            kind: EntryType::Highlight,
+            page: Some(Page::from(5)),
+            location: location(100, 102),
+            creation_date: date(2022, 1, 3, 10, 15, 30),
+            text: "Sing to me of the man, Muse.",
+        },
+    );
+}
+
+#[test]
+fn note() {
+    assert_golden(
+        "note.txt",
+        Golden {
+            title: "The Odyssey",
+            author: "Homer",
+            kind: EntryType::Note,
+            page: Some(Page::from(5)),
+            location: location(102, 102),
+            creation_date: date(2022, 1, 3, 10, 16, 0),
+            text: "Great opening",
+        },
+    );
+}
+
+#[test]
+fn bookmark() {
+    assert_golden(
+        "bookmark.txt",
+        Golden {
+            title: "Dune",
+            author: "Frank Herbert",
+            kind: EntryType::Bookmark,
+            page: None,
+            location: location(2000, 2000),
+            creation_date: date(2022, 2, 1, 21, 0, 0),
+            text: "",
+        },
+    );
+}
+
+#[test]
+fn multiline_highlight() {
+    assert_golden(
+        "multiline.txt",
+        Golden {
+            title: "Dune",
+            author: "Frank Herbert",
+            kind: EntryType::Highlight,
+            page: Some(Page::from(8)),
+            location: location(210, 214),
+            creation_date: date(2022, 2, 1, 21, 5, 12),
+            text: "I must not fear.\nFear is the mind-killer.\n\
+                   Fear is the little-death that brings total obliteration.",
+        },
+    );
+}
+
+#[test]
+fn page_and_location() {
+    assert_golden(
+        "page_location.txt",
+        Golden {
+            title: "Anathem",
+            author: "Neal Stephenson",
+            kind: EntryType::Highlight,
+            page: Some(Page::from(317)),
+            location: location(4850, 4852),
+            creation_date: date(2022, 3, 6, 23, 59, 59),
+            text: "Boredom is a mask that frustration wears.",
+        },
+    );
+}
+
+#[test]
+fn location_only() {
+    assert_golden(
+        "location_only.txt",
+        Golden {
+            title: "Anathem",
+            author: "Neal Stephenson",
+            kind: EntryType::Highlight,
+            page: None,
+            location: location(30, 31),
+            creation_date: date(2022, 3, 6, 0, 0, 1),
+            text: "Any deep way of knowing something is a way of being.",
+        },
+    );
+}
+
+#[test]
+fn page_only() {
+    assert_golden(
+        "page_only.txt",
+        Golden {
+            title: "Atlas of Remote Islands",
+            author: "Judith Schalansky",
+            kind: EntryType::Highlight,
+            page: Some(Page::from(12)),
+            location: None,
+            creation_date: date(2022, 3, 5, 9, 30, 0),
+            text: "Paradise may be an island. It is hell, too.",
+        },
+    );
+}
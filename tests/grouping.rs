@@ -0,0 +1,481 @@
+use clippings_parser::grouping::{
+    attach_notes, attach_notes_within, drop_redundant_notes, filter_books_min, group_by_author,
+    group_by_book, normalize_author, orphan_notes, revised_highlights, sort_books,
+    sort_by_book_and_location, stitch_adjacent, Book, BookOrder, Collection, DEFAULT_STITCH_GAP,
+};
+use clippings_parser::stats::location_percentiles;
+use clippings_parser::{parse_file, parse_reader};
+
+const TWO_BOOKS: &str = "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 200-201 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Fear is the mind-killer.
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 110-112 | Added on Tuesday, January 4, 2022 7:05:00 PM
+
+Tell me of the man of many ways.
+==========
+";
+
+#[test]
+fn collection_borrows_books_in_title_order() {
+    let collection = Collection::new(parse_reader(TWO_BOOKS.as_bytes()).unwrap());
+
+    let books: Vec<_> = collection
+        .books()
+        .map(|book| (book.title(), book.author(), book.entries().len()))
+        .collect();
+    assert_eq!(
+        books,
+        vec![("Dune", "Frank Herbert", 1), ("The Odyssey", "Homer", 2)]
+    );
+    let odyssey = collection.books().nth(1).unwrap();
+    assert_eq!(odyssey.entries()[0].text(), "Sing to me of the man, Muse.");
+}
+
+#[test]
+fn authors_nest_their_books() {
+    let library = format!(
+        "{}\
+Children of Dune (Frank Herbert)
+- Your Highlight on Location 50-51 | Added on Tuesday, February 8, 2022 9:00:00 PM
+
+The mystery of life isn't a problem to solve.
+==========
+",
+        TWO_BOOKS
+    );
+    let authors = group_by_author(parse_reader(library.as_bytes()).unwrap());
+
+    let outline: Vec<(&str, Vec<(&str, usize)>)> = authors
+        .iter()
+        .map(|(author, books)| {
+            let books = books
+                .iter()
+                .map(|book| (book.title.as_str(), book.entries.len()))
+                .collect();
+            (author.as_str(), books)
+        })
+        .collect();
+    assert_eq!(
+        outline,
+        vec![
+            ("Frank Herbert", vec![("Children of Dune", 1), ("Dune", 1)]),
+            ("Homer", vec![("The Odyssey", 2)]),
+        ]
+    );
+}
+
+const ANNOTATED: &str = "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+The Odyssey (Homer)
+- Your Note on Location 102 | Added on Monday, January 3, 2022 10:16:00 AM
+
+sing to me of the  man,  Muse.
+==========
+The Odyssey (Homer)
+- Your Note on Location 102 | Added on Monday, January 3, 2022 10:17:00 AM
+
+Great opening
+==========
+";
+
+#[test]
+fn redundant_notes_are_dropped() {
+    let annotations = attach_notes(parse_reader(ANNOTATED.as_bytes()).unwrap());
+    assert_eq!(annotations[0].notes.len(), 2);
+
+    let annotations = drop_redundant_notes(annotations);
+
+    let notes: Vec<&str> = annotations[0]
+        .notes
+        .iter()
+        .map(|note| note.text())
+        .collect();
+    assert_eq!(notes, vec!["Great opening"]);
+}
+
+#[test]
+fn books_sort_by_author_surname() {
+    let library = "\
+Dune (Frank Herbert)
+- Your Highlight on Location 200-201 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Fear is the mind-killer.
+==========
+Emma (Austen, Jane)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:05:00 PM
+
+Handsome, clever, and rich.
+==========
+Anathem (Neal Stephenson)
+- Your Highlight on Location 30-31 | Added on Monday, February 7, 2022 9:10:00 PM
+
+Boredom is a mask.
+==========
+";
+    let mut books = group_by_book(parse_reader(library.as_bytes()).unwrap());
+    let titles = |books: &[Book]| {
+        books
+            .iter()
+            .map(|book| book.title.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(titles(&books), vec!["Anathem", "Dune", "Emma"]);
+
+    sort_books(&mut books, BookOrder::Author);
+
+    assert_eq!(titles(&books), vec!["Emma", "Dune", "Anathem"]);
+    assert_eq!(normalize_author("Frank Herbert"), "Herbert, Frank");
+}
+
+const SCATTERED_NOTES: &str = "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-110 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 120-125 | Added on Monday, January 3, 2022 10:16:30 AM
+
+Tell me of the man of many ways.
+==========
+The Odyssey (Homer)
+- Your Note on Location 105 | Added on Monday, January 3, 2022 10:17:00 AM
+
+inside the first
+==========
+The Odyssey (Homer)
+- Your Note on Location 118 | Added on Monday, January 3, 2022 10:18:00 AM
+
+just before the second
+==========
+The Odyssey (Homer)
+- Your Note on Location 300 | Added on Monday, January 3, 2022 10:19:00 AM
+
+far from everything
+==========
+";
+
+fn note_texts(annotation: &clippings_parser::grouping::Annotation) -> Vec<&str> {
+    annotation.notes.iter().map(|note| note.text()).collect()
+}
+
+#[test]
+fn notes_attach_by_range_then_nearest_within_window() {
+    let annotations = attach_notes(parse_reader(SCATTERED_NOTES.as_bytes()).unwrap());
+
+    assert_eq!(note_texts(&annotations[0]), vec!["inside the first"]);
+    assert_eq!(note_texts(&annotations[1]), vec!["just before the second"]);
+}
+
+#[test]
+fn window_controls_adjacent_matches() {
+    let annotations = attach_notes_within(parse_reader(SCATTERED_NOTES.as_bytes()).unwrap(), 0);
+
+    assert_eq!(note_texts(&annotations[0]), vec!["inside the first"]);
+    assert!(annotations[1].notes.is_empty());
+}
+
+#[test]
+fn orphan_notes_are_not_attached() {
+    let annotations = attach_notes_within(parse_reader(SCATTERED_NOTES.as_bytes()).unwrap(), 100);
+
+    let attached: usize = annotations
+        .iter()
+        .map(|annotation| annotation.notes.len())
+        .sum();
+    assert_eq!(attached, 2);
+}
+
+#[test]
+fn notes_outside_every_highlight_are_orphans() {
+    let entries = parse_reader(SCATTERED_NOTES.as_bytes()).unwrap();
+
+    let orphans: Vec<&str> = orphan_notes(&entries)
+        .iter()
+        .map(|note| note.text())
+        .collect();
+    assert_eq!(
+        orphans,
+        vec!["just before the second", "far from everything"]
+    );
+}
+
+#[test]
+fn highlights_reworded_at_one_location_are_clustered() {
+    let entries = parse_reader(
+        "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-101 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 100-101 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Fear is the mind-killer.
+==========
+The Odyssey (Homer)
+- Your Note on Location 100 | Added on Monday, January 3, 2022 10:15:50 AM
+
+Not a highlight.
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:16:00 AM
+
+Sing to me of the man, Muse.
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 100-101 | Added on Monday, January 3, 2022 10:17:00 AM
+
+Sing to me of the man
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    let clusters: Vec<Vec<&str>> = revised_highlights(&entries)
+        .iter()
+        .map(|cluster| cluster.iter().map(|entry| entry.text()).collect())
+        .collect();
+    assert_eq!(
+        clusters,
+        vec![vec![
+            "Sing to me of the man",
+            "Sing to me of the man, Muse."
+        ]]
+    );
+}
+
+#[test]
+fn books_below_the_highlight_minimum_are_dropped() {
+    let library = format!(
+        "{}\
+Dune (Frank Herbert)
+- Your Note on Location 201 | Added on Monday, February 7, 2022 9:01:00 PM
+
+Notes do not count.
+==========
+",
+        TWO_BOOKS
+    );
+    let books = filter_books_min(group_by_book(parse_reader(library.as_bytes()).unwrap()), 2);
+
+    let kept: Vec<(&str, usize)> = books
+        .iter()
+        .map(|book| (book.title.as_str(), book.entries.len()))
+        .collect();
+    assert_eq!(kept, vec![("The Odyssey", 2)]);
+}
+
+#[test]
+fn page_only_books_sort_and_group_by_page() {
+    let path = format!(
+        "{}/tests/fixtures/page_only.txt",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut entries = parse_file(path).unwrap();
+    assert!(entries.iter().all(|entry| entry.location().is_none()));
+
+    sort_by_book_and_location(&mut entries);
+
+    let pages: Vec<u64> = entries
+        .iter()
+        .map(|entry| entry.position().start())
+        .collect();
+    assert_eq!(pages, vec![12, 40, 80]);
+    let books = group_by_book(entries);
+    assert_eq!(books.len(), 1);
+    assert_eq!(
+        location_percentiles(&books[0]),
+        vec![(12, 15.0), (40, 50.0), (80, 100.0)]
+    );
+}
+
+const SENTENCES: &str = "\
+Dune (Frank Herbert)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+
+I must not fear.
+==========
+Dune (Frank Herbert)
+- Your Note on Location 11 | Added on Monday, February 7, 2022 9:00:30 PM
+
+The litany
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 12-13 | Added on Monday, February 7, 2022 9:01:00 PM
+
+Fear is the mind-killer.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 16-17 | Added on Monday, February 7, 2022 9:02:00 PM
+
+I will face my fear.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 16-18 | Added on Monday, February 7, 2022 9:03:00 PM
+
+I will face my fear. I will permit it
+==========
+";
+
+#[test]
+fn adjacent_highlights_are_stitched_into_passages() {
+    let entries = parse_reader(SENTENCES.as_bytes()).unwrap();
+
+    let stitched = stitch_adjacent(entries.clone(), DEFAULT_STITCH_GAP);
+
+    let outline: Vec<(u64, u64, &str)> = stitched
+        .iter()
+        .map(|entry| {
+            (
+                entry.position().start(),
+                entry.position().end(),
+                entry.text(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        outline,
+        vec![
+            (10, 13, "I must not fear. Fear is the mind-killer."),
+            (11, 11, "The litany"),
+            (16, 17, "I will face my fear."),
+            (16, 18, "I will face my fear. I will permit it"),
+        ]
+    );
+
+    let wide = stitch_adjacent(entries, 3);
+    assert_eq!(wide.len(), 3);
+    assert_eq!(
+        wide[0].text(),
+        "I must not fear. Fear is the mind-killer. I will face my fear."
+    );
+
+    let nested = parse_reader(
+        "\
+Dune (Frank Herbert)
+- Your Highlight on Location 10-50 | Added on Monday, February 7, 2022 9:00:00 PM
+
+A
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 12-15 | Added on Monday, February 7, 2022 9:01:00 PM
+
+B
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 51-52 | Added on Monday, February 7, 2022 9:02:00 PM
+
+C
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+    let outline: Vec<(u64, u64, String)> = stitch_adjacent(nested, DEFAULT_STITCH_GAP)
+        .iter()
+        .map(|entry| {
+            (
+                entry.position().start(),
+                entry.position().end(),
+                entry.text().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        outline,
+        vec![(10, 52, "A C".to_string()), (12, 15, "B".to_string())]
+    );
+}
+
+#[test]
+fn notes_pair_with_highlights_that_follow_them() {
+    let entries = parse_reader(
+        "\
+The Odyssey (Homer)
+- Your Note on Location 105 | Added on Monday, January 3, 2022 10:17:00 AM
+
+written first
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 100-110 | Added on Monday, January 3, 2022 10:18:00 AM
+
+Fear is the mind-killer.
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 100-110 | Added on Monday, January 3, 2022 10:19:00 AM
+
+Sing to me of the man, Muse.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    let annotations = attach_notes(entries);
+
+    assert!(annotations[0].notes.is_empty());
+    assert_eq!(annotations[1].highlight.title(), "The Odyssey");
+    assert_eq!(note_texts(&annotations[1]), vec!["written first"]);
+}
+
+#[test]
+fn sort_key_orders_by_title_then_position() {
+    let mut entries = parse_reader(
+        "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-105 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Longer.
+==========
+Atlas of Remote Islands (Judith Schalansky)
+- Your Highlight on page 12 | Added on Saturday, March 5, 2022 9:30:00 AM
+
+Paradise may be an island.
+==========
+The Odyssey (Homer)
+- Your Bookmark on page 3 | Added on Monday, January 3, 2022 10:16:00 AM
+
+
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:17:00 AM
+
+Shorter.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    let mut merged = entries.clone();
+    sort_by_book_and_location(&mut merged);
+    entries.sort_by_key(|entry| entry.sort_key());
+    assert_eq!(merged, entries);
+
+    let keys: Vec<(String, u64, u64)> = entries.iter().map(|entry| entry.sort_key()).collect();
+    assert_eq!(
+        keys,
+        vec![
+            ("Atlas of Remote Islands".to_string(), 12, 12),
+            ("The Odyssey".to_string(), 3, 3),
+            ("The Odyssey".to_string(), 100, 102),
+            ("The Odyssey".to_string(), 100, 105),
+        ]
+    );
+}
@@ -0,0 +1,80 @@
+use clippings_parser::{parse_block, parse_reader_lenient, ParseError};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+const TRUNCATED: &str = "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 200-201 | Added on Monday, January 3, 2022 10:20:00 AM
+==========
+";
+
+#[test]
+fn truncated_block_is_reported_with_its_raw_lines() {
+    let report = parse_reader_lenient(TRUNCATED.as_bytes());
+
+    assert_eq!(report.entries.len(), 1);
+    assert_eq!(report.errors.len(), 1);
+    let (line, err, block) = &report.errors[0];
+    assert_eq!(*line, 6);
+    assert!(matches!(err, ParseError::TextNotFound));
+    assert_eq!(
+        block,
+        &vec![
+            "Dune (Frank Herbert)".to_string(),
+            "- Your Highlight on Location 200-201 | Added on Monday, January 3, 2022 10:20:00 AM"
+                .to_string(),
+        ]
+    );
+}
+
+#[test]
+fn repaired_block_parses_on_its_own() {
+    let report = parse_reader_lenient(TRUNCATED.as_bytes());
+    let (_, _, block) = &report.errors[0];
+    assert!(matches!(parse_block(block), Err(ParseError::TextNotFound)));
+
+    let mut repaired = block.clone();
+    repaired.push("Fear is the mind-killer.".to_string());
+    let entry = parse_block(&repaired).unwrap();
+
+    assert_eq!(entry.title(), "Dune");
+    assert_eq!(entry.text(), "Fear is the mind-killer.");
+}
+
+#[test]
+fn the_first_malformed_line_is_reported() {
+    let err = parse_block(&["Dune", "- garbled", "", "Fear is the mind-killer."]).unwrap_err();
+
+    assert!(matches!(err, ParseError::MalformedEntry(line) if line == "Dune"));
+}
+
+#[test]
+fn format_variants_are_tallied_per_entry() {
+    let variants = |name: &str| {
+        let file = std::fs::File::open(fixture(name)).unwrap();
+        parse_reader_lenient(std::io::BufReader::new(file))
+            .format_variants()
+            .unwrap()
+    };
+
+    let pronouns = variants("pronouns.txt");
+    assert_eq!(pronouns.get("my-prefix"), Some(&2));
+    assert_eq!(pronouns.get("no-prefix"), Some(&1));
+    assert_eq!(pronouns.get("clipping-kind"), Some(&1));
+    assert_eq!(pronouns.len(), 3);
+
+    let colons = variants("location_colon.txt");
+    assert_eq!(colons.get("location-colon"), Some(&2));
+    let mixed = variants("mixed_locale.txt");
+    assert_eq!(mixed.get("translated-date-label"), Some(&1));
+    assert_eq!(variants("date_only.txt").get("date-only"), Some(&1));
+    assert_eq!(variants("page_only.txt").get("page-only"), Some(&3));
+    assert!(variants("colored.txt").is_empty());
+}
@@ -0,0 +1,156 @@
+use clippings_parser::{parse_file, parse_reader, EntryType};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn highlight_colors_are_optional() {
+    let entries = parse_file(fixture("colored.txt")).unwrap();
+
+    let colors: Vec<_> = entries.iter().map(|entry| entry.color()).collect();
+    assert_eq!(colors, vec![Some("yellow"), Some("blue"), None]);
+    assert_eq!(entries[0].page().map(|page| page.number()), Some(5));
+    assert_eq!(entries[1].location().unwrap().start(), 110);
+}
+
+#[test]
+fn raw_metadata_line_is_kept_but_not_serialized() {
+    let entries = parse_file(fixture("colored.txt")).unwrap();
+
+    assert_eq!(
+        entries[1].raw_meta(),
+        Some("- Your Highlight (blue) on Location 110-112 | Added on Tuesday, January 4, 2022 7:05:00 PM")
+    );
+    #[cfg(feature = "serde")]
+    assert!(entries[1].to_json_value().get("raw_meta").is_none());
+}
+
+#[test]
+fn location_label_may_have_a_colon() {
+    let entries = parse_file(fixture("location_colon.txt")).unwrap();
+
+    let locations: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.location().unwrap().start(),
+                entry.location().unwrap().end(),
+            )
+        })
+        .collect();
+    assert_eq!(locations, vec![(100, 105), (120, 120)]);
+    assert_eq!(entries[0].page().map(|page| page.number()), Some(5));
+}
+
+#[test]
+fn spaces_around_the_author_are_trimmed() {
+    let entries = parse_reader(
+        "\
+Some Book  (  Jane Doe )
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Trimmed.
+==========
+Some Book (Jane Doe)
+- Your Highlight on Location 20-21 | Added on Monday, February 7, 2022 9:05:00 PM
+
+Clean.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    assert!(entries
+        .iter()
+        .all(|entry| entry.title() == "Some Book" && entry.author() == "Jane Doe"));
+}
+
+#[test]
+fn pronoun_before_the_kind_is_optional() {
+    let entries = parse_file(fixture("pronouns.txt")).unwrap();
+
+    let kinds: Vec<_> = entries.iter().map(|entry| *entry.kind()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            EntryType::Highlight,
+            EntryType::Highlight,
+            EntryType::Highlight,
+            EntryType::Note
+        ]
+    );
+    assert_eq!(entries[2].location().unwrap().start(), 120);
+}
+
+#[test]
+fn en_dash_location_range_parses() {
+    let entries = parse_reader(
+        "\
+Dune (Frank Herbert)
+- Your Highlight on Location 100\u{2013}105 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Fear is the mind-killer.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    let location = entries[0].location().unwrap();
+    assert_eq!((location.start(), location.end()), (100, 105));
+}
+
+#[test]
+fn co_authors_are_split() {
+    let entries = parse_reader(
+        "\
+Good Omens (Terry Pratchett and Neil Gaiman)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+
+It may help to understand human affairs.
+==========
+The Talisman (Stephen King & Peter Straub)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Jack Sawyer.
+==========
+Freakonomics (Steven D. Levitt; Stephen J. Dubner; Alexander Sandy)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Incentives.
+==========
+Emma (Austen, Jane)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Handsome, clever, and rich.
+==========
+The Dispossessed (Le Guin, Ursula K.)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+
+There was a wall.
+==========
+Ender's Game (Orson Scott Card)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+
+The enemy's gate is down.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    let authors: Vec<Vec<&str>> = entries.iter().map(|entry| entry.authors()).collect();
+    assert_eq!(
+        authors,
+        vec![
+            vec!["Terry Pratchett", "Neil Gaiman"],
+            vec!["Stephen King", "Peter Straub"],
+            vec!["Steven D. Levitt", "Stephen J. Dubner", "Alexander Sandy"],
+            vec!["Austen, Jane"],
+            vec!["Le Guin, Ursula K."],
+            vec!["Orson Scott Card"],
+        ]
+    );
+}
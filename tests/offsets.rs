@@ -0,0 +1,33 @@
+use clippings_parser::{parse_file, parse_file_lenient, parse_reader};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+
+const CRLF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/crlf.txt");
+
+#[test]
+fn file_entries_point_back_at_their_blocks() {
+    let entries = parse_file(CRLF).unwrap();
+
+    let offsets: Vec<_> = entries.iter().map(|entry| entry.source_offset()).collect();
+    assert_eq!(offsets, vec![Some(0), Some(150)]);
+
+    let mut file = fs::File::open(CRLF).unwrap();
+    file.seek(SeekFrom::Start(150)).unwrap();
+    let mut block = String::new();
+    file.read_to_string(&mut block).unwrap();
+    assert!(block.starts_with("Dune (Frank Herbert)\r\n"));
+}
+
+#[test]
+fn lenient_file_parse_keeps_offsets() {
+    let report = parse_file_lenient(CRLF).unwrap();
+
+    assert_eq!(report.entries[1].source_offset(), Some(150));
+}
+
+#[test]
+fn reader_entries_have_no_offset() {
+    let entries = parse_reader(fs::read(CRLF).unwrap().as_slice()).unwrap();
+
+    assert!(entries.iter().all(|entry| entry.source_offset().is_none()));
+}
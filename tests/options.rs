@@ -0,0 +1,125 @@
+use clippings_parser::{EntryType, MetaRegex, ParseError, ParseOptions};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn defaults_match_the_free_functions() {
+    let report = ParseOptions::new().parse_file(fixture("crlf.txt")).unwrap();
+
+    assert_eq!(report.entries.len(), 2);
+    assert!(report.errors.is_empty());
+    assert_eq!(report.entries[1].source_offset(), Some(150));
+    assert!(ParseOptions::new()
+        .parse_file(fixture("truncated.txt"))
+        .is_err());
+}
+
+#[test]
+fn lenient_collects_failures() {
+    let report = ParseOptions::new()
+        .lenient(true)
+        .parse_file(fixture("truncated.txt"))
+        .unwrap();
+
+    assert_eq!(report.entries.len(), 1);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].0, 6);
+}
+
+#[test]
+fn lossy_repairs_invalid_utf8() {
+    let input = b"Dune (Frank Herbert)\n- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM\n\nFear \xff\n==========\n";
+
+    assert!(matches!(
+        ParseOptions::new().parse_reader(&input[..]),
+        Err(ParseError::InvalidUtf8 { .. })
+    ));
+    let report = ParseOptions::new()
+        .lossy(true)
+        .parse_reader(&input[..])
+        .unwrap();
+    assert_eq!(report.entries[0].text(), "Fear \u{fffd}");
+}
+
+const CUSTOM_META: &str = r"^\* (?P<kind>\w+)( \((?P<color>\w+)\))?( p\.(?P<page>\d+))?( loc\.(?P<location>[\d-]+))? @ (?P<date>.*)$";
+
+#[test]
+fn custom_metadata_regex_replaces_the_builtin_grammar() {
+    let path = fixture("custom_meta.txt");
+    assert!(ParseOptions::new().parse_file(&path).is_err());
+
+    let report = ParseOptions::new()
+        .meta_regex(CUSTOM_META.parse().unwrap())
+        .parse_file(&path)
+        .unwrap();
+
+    let entries = &report.entries;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].color(), Some("yellow"));
+    assert_eq!(entries[0].page().map(|page| page.number()), Some(8));
+    assert_eq!(entries[0].location().unwrap().end(), 214);
+    assert_eq!(entries[0].text(), "I must not fear.");
+    assert_eq!(*entries[1].kind(), EntryType::Bookmark);
+    assert_eq!(entries[1].page(), None);
+}
+
+#[test]
+fn format_variants_are_unavailable_with_a_custom_metadata_regex() {
+    let custom = ParseOptions::new()
+        .meta_regex(CUSTOM_META.parse().unwrap())
+        .parse_file(fixture("custom_meta.txt"))
+        .unwrap();
+    assert_eq!(custom.format_variants(), None);
+
+    let builtin = ParseOptions::new().parse_file(fixture("crlf.txt")).unwrap();
+    assert_eq!(builtin.format_variants(), Some(Default::default()));
+}
+
+#[test]
+fn metadata_regex_must_compile_and_name_every_group() {
+    let invalid = "(?P<kind>".parse::<MetaRegex>().unwrap_err();
+    assert!(matches!(invalid, ParseError::InvalidMetaRegex(_)));
+
+    let missing = r"^(?P<kind>\S+) (?P<location>\d+) (?P<date>.*)$"
+        .parse::<MetaRegex>()
+        .unwrap_err();
+    assert_eq!(
+        missing.to_string(),
+        "Invalid metadata regex: missing the named group `page`"
+    );
+}
+
+#[test]
+fn a_parenthesized_title_ending_is_read_as_the_author_by_default() {
+    let report = ParseOptions::new()
+        .lenient(true)
+        .parse_file(fixture("no_author.txt"))
+        .unwrap();
+
+    let entry = &report.entries[0];
+    assert_eq!((entry.title(), entry.author()), ("Brackets", "a memoir"));
+    assert!(matches!(
+        report.errors[0].1,
+        ParseError::MalformedEntry(ref line) if line == "Untitled Notes"
+    ));
+}
+
+#[test]
+fn no_author_keeps_the_whole_first_line_as_the_title() {
+    let report = ParseOptions::new()
+        .no_author(true)
+        .parse_file(fixture("no_author.txt"))
+        .unwrap();
+
+    let books: Vec<(&str, &str)> = report
+        .entries
+        .iter()
+        .map(|entry| (entry.title(), entry.author()))
+        .collect();
+    assert_eq!(
+        books,
+        vec![("Brackets (a memoir)", ""), ("Untitled Notes", "")]
+    );
+}
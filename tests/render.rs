@@ -0,0 +1,186 @@
+use clippings_parser::render::{
+    bookmark_marker, escape_control, to_clippings, to_line, to_text, to_text_with,
+};
+use clippings_parser::{parse_block, parse_file, parse_reader, parse_str, Entry, ParseError};
+use std::borrow::Cow;
+
+#[test]
+fn control_characters_are_escaped() {
+    assert_eq!(
+        escape_control("tab\there\u{0b}vt\u{0c}ff\nnext"),
+        "tab\\there\\x0bvt\\x0cff\nnext"
+    );
+}
+
+#[test]
+fn clean_text_is_borrowed() {
+    assert!(matches!(escape_control("plain text"), Cow::Borrowed(_)));
+}
+
+#[test]
+fn text_format_keeps_only_highlight_texts() {
+    let entries = parse_reader(
+        "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+The Odyssey (Homer)
+- Your Note on Location 102 | Added on Monday, January 3, 2022 10:16:00 AM
+
+Great opening
+==========
+The Odyssey (Homer)
+- Your Bookmark on Location 150 | Added on Monday, January 3, 2022 10:17:00 AM
+
+
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 200-202 | Added on Monday, January 3, 2022 10:18:00 AM
+
+Tell me of the man of many ways.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        to_text(&entries),
+        "Sing to me of the man, Muse.\n\nTell me of the man of many ways.\n"
+    );
+}
+
+#[test]
+fn bookmarks_become_marker_lines_on_request() {
+    let entries = parse_reader(
+        "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+The Odyssey (Homer)
+- Your Bookmark on Location 150 | Added on Monday, January 3, 2022 10:17:00 AM
+
+
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        to_text_with(&entries, false),
+        "Sing to me of the man, Muse.\n"
+    );
+    assert_eq!(
+        to_text_with(&entries, true),
+        "Sing to me of the man, Muse.\n\n[Bookmark at location 150]\n"
+    );
+}
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn clippings_output_parses_back_to_the_same_entries() {
+    for name in ["colored.txt", "golden/multiline.txt", "golden/bookmark.txt"] {
+        let entries = parse_file(fixture(name)).unwrap();
+
+        let written = to_clippings(&entries).unwrap();
+        let reparsed = parse_str(&written).unwrap();
+
+        assert_eq!(fields(&reparsed), fields(&entries), "{}", name);
+        assert_eq!(to_clippings(&reparsed).unwrap(), written, "{}", name);
+    }
+}
+
+/// What an entry says, leaving out where it was read from.
+fn fields(entries: &[Entry]) -> Vec<String> {
+    entries
+        .iter()
+        .map(|entry| format!("{} {:?}", entry, entry.color()))
+        .collect()
+}
+
+#[test]
+fn separator_lines_in_text_are_refused() {
+    let entries = parse_str(
+        "\
+Dune (Frank Herbert)
+- Your Note on Location 30 | Added on Wednesday, March 2, 2022 9:00:00 PM
+
+placeholder
+==========
+",
+    )
+    .unwrap();
+    let near_miss = entries[0].clone().with_text("before\n========== \nafter");
+    let separator = entries[0].clone().with_text("before\n==========\nafter");
+
+    assert!(to_clippings(&[near_miss]).is_ok());
+    assert!(matches!(
+        to_clippings(&[separator]),
+        Err(ParseError::SeparatorInText(title)) if title == "Dune"
+    ));
+}
+
+#[test]
+fn line_format_cuts_text_at_the_width() {
+    let entries = parse_str(
+        "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-105 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse,
+the man of twists and turns.
+==========
+The Odyssey (Homer)
+- Your Bookmark on Location 150 | Added on Monday, January 3, 2022 10:20:00 AM
+
+
+==========
+",
+    )
+    .unwrap();
+
+    assert_eq!(
+        to_line(&entries[0], 11),
+        "[H] The Odyssey \u{2014} Homer @100-105 (2022-01-03): Sing to me\u{2026}"
+    );
+    assert_eq!(
+        to_line(&entries[0], 80),
+        "[H] The Odyssey \u{2014} Homer @100-105 (2022-01-03): \
+Sing to me of the man, Muse, the man of twists and turns."
+    );
+    assert_eq!(
+        to_line(&entries[1], 11),
+        "[B] The Odyssey \u{2014} Homer @150 (2022-01-03)"
+    );
+}
+
+#[test]
+fn page_only_entries_round_trip() {
+    let entries = parse_file(fixture("page_only.txt")).unwrap();
+
+    let written = to_clippings(&entries).unwrap();
+
+    assert!(written.contains("- Your Highlight on page 40 | Added on"));
+    assert_eq!(parse_str(&written).unwrap(), entries);
+    assert_eq!(bookmark_marker(&entries[2]), "[Bookmark at page 80]");
+}
+
+#[test]
+fn block_strings_parse_back_with_parse_block() {
+    let mut entries = parse_file(fixture("colored.txt")).unwrap();
+    entries.extend(parse_file(fixture("page_only.txt")).unwrap());
+
+    for entry in &entries {
+        let block = entry.to_block_string();
+        let lines: Vec<&str> = block.lines().collect();
+        assert_eq!(&parse_block(&lines).unwrap(), entry, "{}", block);
+    }
+}
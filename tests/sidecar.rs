@@ -0,0 +1,27 @@
+#![cfg(feature = "sidecar")]
+
+use clippings_parser::sidecar::from_kindle_sidecar;
+use clippings_parser::{parse_file, ParseError};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn sidecar_yields_the_same_entries_as_the_clippings_file() {
+    let json = std::fs::read_to_string(fixture("sidecar.json")).unwrap();
+
+    let entries = from_kindle_sidecar(&json).unwrap();
+
+    assert_eq!(
+        entries,
+        parse_file(fixture("sidecar_clippings.txt")).unwrap()
+    );
+}
+
+#[test]
+fn malformed_sidecar_is_an_error() {
+    let err = from_kindle_sidecar("{\"title\": \"The Odyssey\"}").unwrap_err();
+
+    assert!(matches!(err, ParseError::InvalidSidecar(_)));
+}
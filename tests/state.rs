@@ -0,0 +1,64 @@
+use chrono::NaiveDate;
+use clippings_parser::parse_reader;
+use clippings_parser::state::{newer_than, read_state, write_state};
+use std::fs;
+use std::path::PathBuf;
+
+fn state_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}-{}.state", name, std::process::id()))
+}
+
+#[test]
+fn missing_or_empty_state_keeps_everything() {
+    let path = state_file("missing");
+    assert_eq!(read_state(&path).unwrap(), None);
+
+    let path = state_file("empty");
+    fs::write(&path, "\n").unwrap();
+    assert_eq!(read_state(&path).unwrap(), None);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn stored_timestamp_reads_back_and_filters() {
+    let path = state_file("roundtrip");
+    let newest = NaiveDate::from_ymd_opt(2022, 1, 3)
+        .unwrap()
+        .and_hms_opt(10, 15, 30)
+        .unwrap();
+
+    write_state(&path, newest).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "2022-01-03T10:15:30\n");
+    let last = read_state(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(last, Some(newest));
+
+    let entries = parse_reader(
+        "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 110-112 | Added on Tuesday, January 4, 2022 7:05:00 PM
+
+Tell me of the man of many ways.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+    let newer = newer_than(entries, last);
+    assert_eq!(newer.len(), 1);
+    assert_eq!(newer[0].location().unwrap().start(), 110);
+}
+
+#[test]
+fn garbage_state_is_an_error() {
+    let path = state_file("garbage");
+    fs::write(&path, "yesterday").unwrap();
+
+    assert!(read_state(&path).is_err());
+    fs::remove_file(&path).unwrap();
+}
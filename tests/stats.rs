@@ -0,0 +1,275 @@
+use chrono::NaiveDate;
+use clippings_parser::grouping::group_by_book;
+use clippings_parser::parse_reader;
+use clippings_parser::stats::{
+    daily_counts, distinct_locations, highlight_density, latest_per_book, location_percentiles,
+    longest_streak, rank_by_density, reading_span, stats, type_counts, Stats, TypeCounts,
+};
+
+const ODYSSEY: &str = "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 300-400 | Added on Tuesday, January 4, 2022 7:05:00 PM
+
+Tell me of the man of many ways.
+==========
+";
+
+const SINGLE_SPOT: &str = "\
+Dune (Frank Herbert)
+- Your Highlight on Location 0 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Fear is the mind-killer.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 0 | Added on Monday, February 7, 2022 9:01:00 PM
+
+Fear is the little-death.
+==========
+";
+
+#[test]
+fn percentiles_use_the_largest_location_as_length() {
+    let books = group_by_book(parse_reader(ODYSSEY.as_bytes()).unwrap());
+
+    assert_eq!(
+        location_percentiles(&books[0]),
+        vec![(100, 25.0), (300, 75.0)]
+    );
+}
+
+#[test]
+fn percentiles_of_a_book_without_extent_are_zero() {
+    let books = group_by_book(parse_reader(SINGLE_SPOT.as_bytes()).unwrap());
+
+    assert_eq!(location_percentiles(&books[0]), vec![(0, 0.0), (0, 0.0)]);
+}
+
+#[test]
+fn latest_per_book_breaks_ties_by_location() {
+    let entries = parse_reader(
+        "\
+The Odyssey (Homer)
+- Your Highlight on Location 300-400 | Added on Tuesday, January 4, 2022 7:05:00 PM
+
+Tell me of the man of many ways.
+==========
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 20-21 | Added on Monday, February 7, 2022 9:00:00 PM
+
+I must not fear.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Fear is the mind-killer.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    let latest: Vec<&str> = latest_per_book(&entries)
+        .iter()
+        .map(|entry| entry.text())
+        .collect();
+    assert_eq!(
+        latest,
+        vec!["I must not fear.", "Tell me of the man of many ways."]
+    );
+}
+
+#[test]
+fn stats_count_books_and_kinds() {
+    let entries = parse_reader(
+        "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-102 | Added on Monday, January 3, 2022 10:15:30 AM
+
+Sing to me of the man, Muse.
+==========
+The Odyssey (Homer)
+- Your Note on Location 102 | Added on Monday, January 3, 2022 10:16:00 AM
+
+Great opening
+==========
+Dune (Frank Herbert)
+- Your Bookmark on Location 2000 | Added on Tuesday, February 1, 2022 9:00:00 PM
+
+
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        stats(&entries),
+        Stats {
+            entries: 3,
+            books: 2,
+            highlights: 1,
+            notes: 1,
+            bookmarks: 1,
+            articles: 0,
+        }
+    );
+}
+
+#[test]
+fn reading_span_and_streak_count_highlight_days() {
+    let entries = parse_reader(
+        "\
+Dune (Frank Herbert)
+- Your Highlight on Location 10-11 | Added on Monday, February 28, 2022 9:00:00 PM
+
+Fear is the mind-killer.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 20-21 | Added on Tuesday, March 1, 2022 8:00:00 AM
+
+I must not fear.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 22-23 | Added on Tuesday, March 1, 2022 9:00:00 PM
+
+Fear is the little-death.
+==========
+Dune (Frank Herbert)
+- Your Note on Location 30 | Added on Wednesday, March 2, 2022 9:00:00 PM
+
+Notes do not extend a streak
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 40-41 | Added on Friday, March 4, 2022 9:00:00 PM
+
+I will face my fear.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        reading_span(&entries),
+        Some((
+            NaiveDate::from_ymd_opt(2022, 2, 28).unwrap(),
+            NaiveDate::from_ymd_opt(2022, 3, 4).unwrap()
+        ))
+    );
+    assert_eq!(longest_streak(&entries), 2);
+    assert_eq!(reading_span(&[]), None);
+    assert_eq!(longest_streak(&[]), 0);
+}
+
+#[test]
+fn density_ranks_books_by_highlights_per_span() {
+    let mut entries = parse_reader(ODYSSEY.as_bytes()).unwrap();
+    entries.extend(parse_reader(SINGLE_SPOT.as_bytes()).unwrap());
+    let books = group_by_book(entries);
+
+    assert_eq!(highlight_density(&books[1]), 2.0 / 300.0 * 1000.0);
+    assert_eq!(highlight_density(&books[0]), 0.0);
+    let ranked: Vec<&str> = rank_by_density(&books)
+        .iter()
+        .map(|(book, _)| book.title.as_str())
+        .collect();
+    assert_eq!(ranked, vec!["The Odyssey", "Dune"]);
+}
+
+#[test]
+fn distinct_locations_ignore_repeats_and_notes() {
+    let mut entries = parse_reader(ODYSSEY.as_bytes()).unwrap();
+    entries.extend(
+        parse_reader(
+            "\
+The Odyssey (Homer)
+- Your Highlight on Location 100-103 | Added on Wednesday, January 5, 2022 7:05:00 PM
+
+Sing to me of the man, Muse, the man of twists and turns.
+==========
+The Odyssey (Homer)
+- Your Note on Location 500 | Added on Wednesday, January 5, 2022 7:06:00 PM
+
+A note is not a spot
+==========
+"
+            .as_bytes(),
+        )
+        .unwrap(),
+    );
+    let books = group_by_book(entries);
+
+    assert_eq!(distinct_locations(&books[0]), 2);
+}
+
+#[test]
+fn type_counts_include_articles() {
+    let entries = parse_reader(
+        "\
+Why Rust? (Example Blog)
+- Your Article on Location 1-40 | Added on Tuesday, February 1, 2022 9:00:00 PM
+
+An article clipped from the web.
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 10-11 | Added on Monday, February 7, 2022 9:00:00 PM
+
+Fear is the mind-killer.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        type_counts(&entries),
+        TypeCounts {
+            highlights: 1,
+            notes: 0,
+            bookmarks: 0,
+            articles: 1,
+        }
+    );
+    assert_eq!(stats(&entries).articles, 1);
+}
+
+#[test]
+fn daily_counts_skip_days_without_entries() {
+    let entries = parse_reader(
+        "\
+Dune (Frank Herbert)
+- Your Highlight on Location 10-11 | Added on Monday, February 28, 2022 9:00:00 PM
+
+Fear is the mind-killer.
+==========
+Dune (Frank Herbert)
+- Your Note on Location 11 | Added on Monday, February 28, 2022 9:01:00 PM
+
+Notes count too
+==========
+Dune (Frank Herbert)
+- Your Highlight on Location 40-41 | Added on Friday, March 4, 2022 9:00:00 PM
+
+I will face my fear.
+==========
+"
+        .as_bytes(),
+    )
+    .unwrap();
+
+    let day = |month, day| NaiveDate::from_ymd_opt(2022, month, day).unwrap();
+    assert_eq!(
+        daily_counts(&entries).into_iter().collect::<Vec<_>>(),
+        vec![(day(2, 28), 2), (day(3, 4), 1)]
+    );
+}
@@ -0,0 +1,39 @@
+use clippings_parser::parse_file;
+
+const BLANK_LINES: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/blank_lines.txt"
+);
+
+#[test]
+fn text_follows_zero_or_one_blank_lines() {
+    let entries = parse_file(BLANK_LINES).unwrap();
+
+    let texts: Vec<&str> = entries.iter().map(|entry| entry.text()).collect();
+    assert_eq!(
+        texts,
+        vec![
+            "Sing to me of the man, Muse.",
+            "Tell me of the man of many ways,\nwho was driven far journeys.",
+            "",
+        ]
+    );
+}
+
+#[test]
+fn shared_indentation_is_removed() {
+    let entries = parse_file(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/indented.txt"
+    ))
+    .unwrap();
+
+    let texts: Vec<&str> = entries.iter().map(|entry| entry.text()).collect();
+    assert_eq!(
+        texts,
+        vec![
+            "Sing to me of the man, Muse.",
+            "A list:\n  - first\n\n  - second",
+        ]
+    );
+}
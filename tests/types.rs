@@ -0,0 +1,46 @@
+use clippings_parser::{Location, Page};
+use std::str::FromStr;
+
+#[test]
+fn page_from_number() {
+    assert_eq!(Page::from(42).number(), 42);
+}
+
+#[test]
+fn location_from_ordered_pair() {
+    let location = Location::try_from((100, 105)).unwrap();
+
+    assert_eq!((location.start(), location.end()), (100, 105));
+    assert_eq!(Location::try_from((7, 7)).unwrap().end(), 7);
+}
+
+#[test]
+fn location_rejects_reversed_ranges() {
+    assert!(Location::try_from((105, 100)).is_err());
+    assert!(Location::from_str("105-100").is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn page_serializes_as_a_plain_number() {
+    assert_eq!(serde_json::to_string(&Page::from(5)).unwrap(), "5");
+}
+
+#[test]
+fn location_contains_and_distance() {
+    let location = Location::try_from((100, 105)).unwrap();
+
+    assert!(location.contains(100) && location.contains(105));
+    assert!(!location.contains(106));
+    assert_eq!(location.distance(103), 0);
+    assert_eq!(location.distance(97), 3);
+    assert_eq!(location.distance(108), 3);
+}
+
+#[test]
+fn location_ranges_accept_unicode_dashes() {
+    let location = Location::from_str("100\u{2013}105").unwrap();
+    assert_eq!((location.start(), location.end()), (100, 105));
+    assert_eq!(Location::from_str("100\u{2014}105").unwrap().end(), 105);
+    assert!(Location::from_str("100\u{2013}").is_err());
+}
@@ -0,0 +1,22 @@
+use clippings_parser::filters::strip_bidi_controls;
+use clippings_parser::parse_file;
+
+const ARABIC: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/arabic.txt");
+
+#[test]
+fn arabic_title_and_author_are_preserved_byte_for_byte() {
+    let entries = parse_file(ARABIC).unwrap();
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].title(), "ديوان المتنبّي");
+    assert_eq!(entries[0].author(), "\u{200F}أبو الطيّب المتنبّي");
+    assert_eq!(entries[0].text(), "الخيل والليل والبيداء تعرفني");
+}
+
+#[test]
+fn stripping_bidi_controls_is_opt_in() {
+    let entries = strip_bidi_controls(parse_file(ARABIC).unwrap());
+
+    assert_eq!(entries[0].author(), "أبو الطيّب المتنبّي");
+    assert_eq!(entries[0].title(), "ديوان المتنبّي");
+}